@@ -14,6 +14,7 @@ data class IllakiyaSettings(
     val darkTheme: Boolean = true,
     val sandhiEnabled: Boolean = true,
     val swipeNedilEnabled: Boolean = true,
+    val incognito: Boolean = false,
     val soundFeedback: Boolean = true,
     val hapticFeedback: Boolean = true,
     val layout: String = "PM0100",
@@ -31,6 +32,7 @@ class PreferencesManager(private val context: Context) {
         private const val KEY_DARK_THEME = "dark_theme"
         private const val KEY_SANDHI_ENABLED = "sandhi_enabled"
         private const val KEY_SWIPE_NEDIL = "swipe_nedil_enabled"
+        private const val KEY_INCOGNITO = "incognito"
         private const val KEY_SOUND_FEEDBACK = "sound_feedback"
         private const val KEY_HAPTIC_FEEDBACK = "haptic_feedback"
         private const val KEY_LAYOUT = "layout"
@@ -53,6 +55,7 @@ class PreferencesManager(private val context: Context) {
             darkTheme = prefs.getBoolean(KEY_DARK_THEME, true),
             sandhiEnabled = prefs.getBoolean(KEY_SANDHI_ENABLED, true),
             swipeNedilEnabled = prefs.getBoolean(KEY_SWIPE_NEDIL, true),
+            incognito = prefs.getBoolean(KEY_INCOGNITO, false),
             soundFeedback = prefs.getBoolean(KEY_SOUND_FEEDBACK, true),
             hapticFeedback = prefs.getBoolean(KEY_HAPTIC_FEEDBACK, true),
             layout = prefs.getString(KEY_LAYOUT, "PM0100") ?: "PM0100",
@@ -78,6 +81,11 @@ class PreferencesManager(private val context: Context) {
         _settings.value = _settings.value.copy(swipeNedilEnabled = enabled)
     }
     
+    fun setIncognito(enabled: Boolean) {
+        prefs.edit().putBoolean(KEY_INCOGNITO, enabled).apply()
+        _settings.value = _settings.value.copy(incognito = enabled)
+    }
+    
     fun setSoundFeedback(enabled: Boolean) {
         prefs.edit().putBoolean(KEY_SOUND_FEEDBACK, enabled).apply()
         _settings.value = _settings.value.copy(soundFeedback = enabled)
@@ -1,7 +1,12 @@
 package com.yazhi.illakiya.service
 
 import android.inputmethodservice.InputMethodService
+import android.os.Handler
+import android.os.Looper
+import android.text.InputType
+import android.util.Log
 import android.view.View
+import android.view.inputmethod.EditorInfo
 import androidx.compose.foundation.layout.*
 import androidx.compose.material3.*
 import androidx.compose.runtime.*
@@ -14,44 +19,78 @@ import androidx.savedstate.SavedStateRegistryController
 import androidx.savedstate.SavedStateRegistryOwner
 import com.yazhi.illakiya.ui.theme.IllakiyaTheme
 import com.yazhi.illakiya.ui.keyboard.KeyboardView
-import com.yazhi.illakiya.ui.keyboard.Key
+import com.yazhi.illakiya.core.BackspaceMode
+import com.yazhi.illakiya.core.EditResult
+import com.yazhi.illakiya.core.InputContext
+import com.yazhi.illakiya.core.InputEvent
+import com.yazhi.illakiya.core.InputKind
 import com.yazhi.illakiya.core.KeyboardEngine
+import com.yazhi.illakiya.core.ReadyListener
+import com.yazhi.illakiya.data.PreferencesManager
 
 class IllakiyaIME : InputMethodService(), LifecycleOwner, SavedStateRegistryOwner {
 
     private val lifecycleRegistry = LifecycleRegistry(this)
     private val savedStateRegistryController = SavedStateRegistryController.create(this)
     
-    private val engine = KeyboardEngine()
+    /** Set in onCreate; unset if the layout failed to load */
+    private lateinit var engine: KeyboardEngine
+    private var loadError: Throwable? = null
+    private val engineLoaded get() = ::engine.isInitialized
+
+    /** A composing region is on screen */
+    private var composingShown = false
+
+    /** The next selection update reports our own edit */
+    private var ownEditPending = false
 
     override fun onCreate() {
         super.onCreate()
         savedStateRegistryController.performRestore(null)
         lifecycleRegistry.handleLifecycleEvent(Lifecycle.Event.ON_CREATE)
+        // A broken layout must not take the IME service down with it
+        runCatching { KeyboardEngine() }
+            .onSuccess { engine = it }
+            .onFailure {
+                loadError = it
+                Log.e(TAG, "Illakiya engine failed to load", it)
+            }
+        if (engineLoaded) {
+            applyPrivacySettings()
+            restoreEngineState()
+        }
     }
 
     override fun onCreateInputView(): View {
         lifecycleRegistry.handleLifecycleEvent(Lifecycle.Event.ON_START)
-        
+        if (!engineLoaded) {
+            return loadErrorView()
+        }
+
         return ComposeView(this).apply {
             setContent {
                 IllakiyaTheme {
                     var suggestions by remember { mutableStateOf<List<String>>(emptyList()) }
-                    var pendingChar by remember { mutableStateOf<String?>(null) }
                     var sandhiHint by remember { mutableStateOf<String?>(null) }
-                    
-                    val keys = remember {
-                        listOf(
-                            Key("அ", "z"), Key("இ", "x"), Key("உ", "c"),
-                            Key("எ", "v"), Key("ஒ", "b"), Key("ஐ", "n"), Key("ஔ", "m"),
-                            Key("க்", "q"), Key("ங்", "w"), Key("ச்", "e"),
-                            Key("ஞ்", "r"), Key("ட்", "t"), Key("ண்", "y"),
-                            Key("த்", "u"), Key("ந்", "i"), Key("ப்", "o"), Key("ம்", "p"),
-                            Key("ய்", "a"), Key("ர்", "s"), Key("ல்", "d"),
-                            Key("வ்", "f"), Key("ழ்", "g"), Key("ள்", "h"),
-                            Key("ற்", "j"), Key("ன்", "k"), Key("ஃ", "l"),
-                            Key("⇧", "nedil"), Key("␣", "space"), Key("⌫", "backspace")
-                        )
+                    var keyboard by remember { mutableStateOf(engine.getKeyboardModel()) }
+                    var keyProbabilities by remember { mutableStateOf(engine.getKeyProbabilities()) }
+
+                    // Re-read everything the UI shows after an engine call
+                    fun refresh() {
+                        keyboard = engine.getKeyboardModel()
+                        keyProbabilities = engine.getKeyProbabilities()
+                        suggestions = engine.getSuggestions(5u)
+                        sandhiHint = engine.getSandhiSuggestion()
+                    }
+
+                    // The dictionary loads in the background; typing works
+                    // meanwhile, and suggestions appear once it is ready
+                    LaunchedEffect(Unit) {
+                        engine.setReadyListener(object : ReadyListener {
+                            override fun onReady() {
+                                Handler(Looper.getMainLooper()).post { refresh() }
+                            }
+                        })
                     }
 
                     Column {
@@ -60,39 +99,66 @@ class IllakiyaIME : InputMethodService(), LifecycleOwner, SavedStateRegistryOwne
                             suggestions = suggestions,
                             sandhiHint = sandhiHint,
                             onSuggestionTap = { suggestion ->
-                                val committed = engine.acceptSuggestion(suggestion)
-                                commitText(committed)
-                                suggestions = engine.getSuggestions(5u)
-                                sandhiHint = engine.getSandhiSuggestion()
+                                applyEdit(engine.acceptSuggestion(suggestion))
+                                refresh()
+                            },
+                            onSandhiTap = {
+                                applyEdit(engine.applySandhi())
+                                refresh()
                             }
                         )
 
-                        // Keyboard grid
-                        KeyboardView(keys = keys, pendingKey = pendingChar) { keyCode ->
-                            val output = engine.processInput(keyCode)
-                            
-                            if (keyCode == "backspace") {
-                                deleteLastChar()
-                            } else if (output.isNotEmpty() && output != "\u0008") {
-                                commitText(output)
+                        // Keyboard rendered from the Rust core's layout model
+                        KeyboardView(
+                            model = keyboard,
+                            keyProbabilities = keyProbabilities,
+                            onKeyLongPress = { key ->
+                                // Long press types the popup letter (grantha, nedil)
+                                key.popupLayer?.let { layer ->
+                                    engine.processEvent(InputEvent.SetLayer(layer, false))
+                                    applyEdit(engine.processEvent(InputEvent.KeyTap(key.key)))
+                                    refresh()
+                                }
+                            },
+                            // The engine weighs the tap position against the dictionary
+                            onTap = { x, y ->
+                                applyEdit(engine.processTap(x, y))
+                                refresh()
+                            },
+                            onKeyPress = { keyCode ->
+                                val event = if (keyCode == "backspace") {
+                                    InputEvent.Backspace(BackspaceMode.CHAR)
+                                } else {
+                                    InputEvent.KeyTap(keyCode)
+                                }
+                                applyEdit(engine.processEvent(event))
+                                refresh()
                             }
-                            
-                            // Update state
-                            pendingChar = engine.getPending()
-                            suggestions = engine.getSuggestions(5u)
-                            sandhiHint = engine.getSandhiSuggestion()
-                        }
+                        )
                     }
                 }
             }
         }
     }
 
+    /** Shown instead of the keys; the user can switch keyboards from the system bar */
+    private fun loadErrorView(): View = ComposeView(this).apply {
+        setContent {
+            IllakiyaTheme {
+                Text(
+                    text = "இல்லக்கியா விசைப்பலகையை ஏற்ற முடியவில்லை: ${loadError?.message.orEmpty()}",
+                    modifier = Modifier.fillMaxWidth().padding(16.dp)
+                )
+            }
+        }
+    }
+
     @Composable
     private fun SuggestionStrip(
         suggestions: List<String>,
         sandhiHint: String?,
-        onSuggestionTap: (String) -> Unit
+        onSuggestionTap: (String) -> Unit,
+        onSandhiTap: () -> Unit
     ) {
         Row(
             modifier = Modifier
@@ -104,7 +170,7 @@ class IllakiyaIME : InputMethodService(), LifecycleOwner, SavedStateRegistryOwne
             // Show sandhi hint first (if any)
             sandhiHint?.let { hint ->
                 SuggestionChip(
-                    onClick = { onSuggestionTap(hint) },
+                    onClick = onSandhiTap,
                     label = { Text("⚡ $hint") }
                 )
             }
@@ -119,20 +185,136 @@ class IllakiyaIME : InputMethodService(), LifecycleOwner, SavedStateRegistryOwne
         }
     }
 
-    private fun commitText(text: String) {
-        currentInputConnection?.commitText(text, 1)
+    private fun applyEdit(edit: EditResult) {
+        val ic = currentInputConnection ?: return
+        ic.beginBatchEdit()
+        // Drop the old composing region; the edit carries its replacement
+        ic.setComposingText("", 1)
+        if (edit.deleteBefore > 0u || edit.deleteAfter > 0u) {
+            ic.deleteSurroundingText(edit.deleteBefore.toInt(), edit.deleteAfter.toInt())
+        }
+        if (edit.commit.isNotEmpty()) {
+            ic.commitText(edit.commit, 1)
+        }
+        if (edit.composing.isNotEmpty()) {
+            ic.setComposingText(edit.composing, 1)
+        }
+        ic.endBatchEdit()
+        // Only edits that change the text move the selection
+        ownEditPending = composingShown || edit.composing.isNotEmpty() || edit.commit.isNotEmpty() ||
+            edit.deleteBefore > 0u || edit.deleteAfter > 0u
+        composingShown = edit.composing.isNotEmpty()
     }
 
-    private fun deleteLastChar() {
-        currentInputConnection?.deleteSurroundingText(1, 0)
+    override fun onStartInput(attribute: EditorInfo?, restarting: Boolean) {
+        super.onStartInput(attribute, restarting)
+        if (!engineLoaded) return
+        // Each input field keeps its own buffer and word history
+        applyPrivacySettings()
+        attribute?.let {
+            engine.switchSession("${it.packageName}:${it.fieldId}")
+            // Views without an id cannot be told apart: start those empty
+            if (it.fieldId == View.NO_ID) engine.reset()
+            engine.setInputContext(inputContextFor(it))
+        }
+        syncWithCursor()
     }
 
+    /** Settings may have changed in SettingsActivity; re-read on every field */
+    private fun applyPrivacySettings() {
+        engine.setIncognito(PreferencesManager(this).settings.value.incognito)
+    }
+
+    /** Map Android's field description onto the engine's input context */
+    private fun inputContextFor(info: EditorInfo): InputContext {
+        val cls = info.inputType and InputType.TYPE_MASK_CLASS
+        val variation = info.inputType and InputType.TYPE_MASK_VARIATION
+        val kind = when {
+            cls == InputType.TYPE_CLASS_NUMBER || cls == InputType.TYPE_CLASS_PHONE -> InputKind.NUMBER
+            cls != InputType.TYPE_CLASS_TEXT -> InputKind.TEXT
+            variation == InputType.TYPE_TEXT_VARIATION_PASSWORD ||
+                variation == InputType.TYPE_TEXT_VARIATION_VISIBLE_PASSWORD ||
+                variation == InputType.TYPE_TEXT_VARIATION_WEB_PASSWORD -> InputKind.PASSWORD
+            variation == InputType.TYPE_TEXT_VARIATION_URI -> InputKind.URL
+            variation == InputType.TYPE_TEXT_VARIATION_EMAIL_ADDRESS ||
+                variation == InputType.TYPE_TEXT_VARIATION_WEB_EMAIL_ADDRESS -> InputKind.EMAIL
+            variation == InputType.TYPE_TEXT_VARIATION_FILTER -> InputKind.SEARCH
+            info.inputType and InputType.TYPE_TEXT_FLAG_MULTI_LINE != 0 -> InputKind.LONG_FORM
+            else -> InputKind.TEXT
+        }
+        val noLearning = info.imeOptions and EditorInfo.IME_FLAG_NO_PERSONALIZED_LEARNING != 0
+        return InputContext(kind = kind, noLearning = noLearning)
+    }
+
+    override fun onUpdateSelection(
+        oldSelStart: Int, oldSelEnd: Int,
+        newSelStart: Int, newSelEnd: Int,
+        candidatesStart: Int, candidatesEnd: Int
+    ) {
+        super.onUpdateSelection(oldSelStart, oldSelEnd, newSelStart, newSelEnd, candidatesStart, candidatesEnd)
+        if (!engineLoaded) return
+        // Our own edits leave the cursor at the end of the composing region,
+        // or at a plain caret right after a commit
+        val ownEdit = ownEditPending
+        ownEditPending = false
+        val cursorAtComposing = candidatesStart >= 0 && newSelEnd == candidatesEnd
+        if (newSelStart == newSelEnd && !cursorAtComposing && !ownEdit) {
+            syncWithCursor()
+        }
+    }
+
+    /** Re-enter the word around the cursor after it moved outside our edits */
+    private fun syncWithCursor() {
+        val ic = currentInputConnection ?: return
+        ic.finishComposingText()
+        composingShown = false
+        val before = ic.getTextBeforeCursor(CONTEXT_CHARS, 0)?.toString() ?: ""
+        val after = ic.getTextAfterCursor(CONTEXT_CHARS, 0)?.toString() ?: ""
+        applyEdit(engine.setSurroundingText(before, after))
+    }
+
+    override fun onFinishInput() {
+        if (!engineLoaded) {
+            super.onFinishInput()
+            return
+        }
+        // Leaving the field is a commit point for the composing word
+        applyEdit(engine.finishComposing())
+        // Only the field being left is persisted, then forgotten, so
+        // sessions do not pile up for every field ever typed in
+        saveEngineState()
+        engine.destroySession(engine.activeSession())
+        super.onFinishInput()
+    }
+
+    /** Engine state survives process death via a snapshot file */
+    private fun saveEngineState() {
+        runCatching { engineStateFile().writeText(engine.snapshot()) }
+    }
+
+    private fun restoreEngineState() {
+        runCatching {
+            val file = engineStateFile()
+            if (file.exists()) engine.restore(file.readText())
+        }
+    }
+
+    private fun engineStateFile() = java.io.File(filesDir, ENGINE_STATE_FILE)
+
     override fun onDestroy() {
         lifecycleRegistry.handleLifecycleEvent(Lifecycle.Event.ON_DESTROY)
-        engine.reset()
+        // onFinishInput has saved the last field
+        if (engineLoaded) engine.reset()
         super.onDestroy()
     }
 
+    companion object {
+        private const val TAG = "IllakiyaIME"
+        /** Characters of surrounding text handed to the engine on cursor moves */
+        private const val CONTEXT_CHARS = 64
+        private const val ENGINE_STATE_FILE = "engine_state.json"
+    }
+
     override val lifecycle: Lifecycle get() = lifecycleRegistry
     override val savedStateRegistry: SavedStateRegistry 
         get() = savedStateRegistryController.savedStateRegistry
@@ -1,30 +1,36 @@
 package com.yazhi.illakiya.ui.keyboard
 
+import androidx.compose.foundation.background
+import androidx.compose.foundation.gestures.detectTapGestures
+import androidx.compose.foundation.layout.Box
+import androidx.compose.foundation.layout.Column
+import androidx.compose.foundation.layout.Row
+import androidx.compose.foundation.layout.fillMaxWidth
+import androidx.compose.foundation.layout.height
+import androidx.compose.foundation.layout.padding
+import androidx.compose.foundation.shape.RoundedCornerShape
+import androidx.compose.material3.Text
 import androidx.compose.runtime.Composable
+import androidx.compose.runtime.getValue
+import androidx.compose.runtime.mutableStateOf
+import androidx.compose.runtime.remember
+import androidx.compose.runtime.setValue
+import androidx.compose.ui.Alignment
 import androidx.compose.ui.Modifier
-import androidx.compose.foundation.lazy.grid.LazyVerticalGrid
-import androidx.compose.foundation.lazy.grid.GridCells
-import androidx.compose.foundation.lazy.grid.items
-import androidx.compose.material3.Button
-import androidx.compose.material3.ButtonDefaults
-import androidx.compose.material3.Text
-import androidx.compose.foundation.layout.padding
+import androidx.compose.ui.draw.alpha
+import androidx.compose.ui.geometry.Offset
+import androidx.compose.ui.geometry.Rect
+import androidx.compose.ui.input.pointer.pointerInput
+import androidx.compose.ui.layout.boundsInRoot
+import androidx.compose.ui.layout.onGloballyPositioned
+import androidx.compose.ui.text.font.FontWeight
 import androidx.compose.ui.unit.dp
 import androidx.compose.ui.unit.sp
-import androidx.compose.foundation.background
-import androidx.compose.foundation.shape.RoundedCornerShape
-import androidx.compose.ui.graphics.Color
-import androidx.compose.material3.MaterialTheme
+import com.yazhi.illakiya.core.KeyModel
+import com.yazhi.illakiya.core.KeyboardModel
 import com.yazhi.illakiya.ui.theme.*
-import androidx.compose.ui.text.font.FontWeight
-
-// Key Data Structure (mirrors PM0100 JSON)
-data class Key(
-    val label: String,
-    val value: String,
-    val type: KeyType = KeyType.VOWEL
-)
 
+// Key colour class, derived from the label the Rust core renders
 enum class KeyType {
     VALLINAM,    // Consonants (hard) - Terracotta
     IDAIYINAM,   // Consonants (soft) - Muted Terracotta
@@ -33,39 +39,79 @@ enum class KeyType {
     SPECIAL      // Space, Backspace, etc. - Gradient
 }
 
+fun keyTypeFor(key: KeyModel): KeyType = when (key.primary.firstOrNull()) {
+    in "கசடதபற" -> KeyType.VALLINAM
+    in "யரலவழள" -> KeyType.IDAIYINAM
+    in "ஙஞணநமன" -> KeyType.MELLINAM
+    in "அஆஇஈஉஊஎஏஐஒஓஔ" -> KeyType.VOWEL
+    else -> KeyType.SPECIAL
+}
+
+// Next-key probability from which a key label is emphasised
+private const val LIKELY_KEY = 0.2f
+
+/**
+ * Renders the keyboard model from the Rust core (rows, widths, labels,
+ * validity), so the layout is defined once in pm0100.json.
+ *
+ * With [onTap] set, taps are reported as coordinates normalised to the
+ * keyboard area, so the engine can decide which key was meant; otherwise
+ * [onKeyPress] receives the key that was hit.
+ */
 @Composable
 fun KeyboardView(
-    keys: List<Key>,
+    model: KeyboardModel,
     onKeyPress: (String) -> Unit,
-    keySize: Int = 48,
-    fontSize: Int = 18,
-    pendingKey: String? = null
+    onTap: ((x: Float, y: Float) -> Unit)? = null,
+    keyProbabilities: Map<String, Float> = emptyMap(),
+    onKeyLongPress: (KeyModel) -> Unit = {},
+    keyHeight: Int = 48,
+    fontSize: Int = 18
 ) {
-    LazyVerticalGrid(
-        columns = GridCells.Fixed(6),
-        modifier = Modifier.padding(4.dp)
+    var keyboardBounds by remember { mutableStateOf(Rect.Zero) }
+    Column(
+        modifier = Modifier
+            .padding(4.dp)
+            .onGloballyPositioned { keyboardBounds = it.boundsInRoot() }
     ) {
-        items(keys) { key ->
-            KeyButton(
-                key = key,
-                onClick = { onKeyPress(key.value) },
-                size = keySize,
-                fontSize = fontSize,
-                isPending = key.value == pendingKey
-            )
+        model.rows.forEach { row ->
+            Row(modifier = Modifier.fillMaxWidth()) {
+                row.forEach { key ->
+                    KeyButton(
+                        key = key,
+                        probability = keyProbabilities[key.key],
+                        onTap = { position ->
+                            if (onTap != null && !keyboardBounds.isEmpty) {
+                                onTap(
+                                    (position.x - keyboardBounds.left) / keyboardBounds.width,
+                                    (position.y - keyboardBounds.top) / keyboardBounds.height
+                                )
+                            } else {
+                                onKeyPress(key.key)
+                            }
+                        },
+                        onLongClick = { onKeyLongPress(key) },
+                        height = keyHeight,
+                        fontSize = fontSize,
+                        modifier = Modifier.weight(key.width)
+                    )
+                }
+            }
         }
     }
 }
 
 @Composable
 fun KeyButton(
-    key: Key,
-    onClick: () -> Unit,
-    size: Int = 48,
-    fontSize: Int = 18,
-    isPending: Boolean = false
+    key: KeyModel,
+    probability: Float?,
+    onTap: (positionInRoot: Offset) -> Unit,
+    onLongClick: () -> Unit,
+    modifier: Modifier = Modifier,
+    height: Int = 48,
+    fontSize: Int = 18
 ) {
-    val (backgroundColor, textColor) = when (key.type) {
+    val (backgroundColor, textColor) = when (keyTypeFor(key)) {
         KeyType.VALLINAM -> {
             // Warm terracotta for main consonants
             Pair(VallinamBase, SangamTextLight)
@@ -87,31 +133,47 @@ fun KeyButton(
             Pair(SangamBgMedium, SangamTextLight)
         }
     }
-    
-    Button(
-        onClick = onClick,
-        modifier = Modifier
+
+    var keyBounds by remember { mutableStateOf(Rect.Zero) }
+    Box(
+        modifier = modifier
             .padding(2.dp)
-            .then(
-                if (isPending)
-                    Modifier.background(
-                        color = SangamPrimary.copy(alpha = 0.3f),
-                        shape = RoundedCornerShape(8.dp)
-                    )
-                else Modifier
-            ),
-        colors = ButtonDefaults.buttonColors(
-            containerColor = backgroundColor,
-            contentColor = textColor
-        ),
-        shape = RoundedCornerShape(8.dp)
+            .height(height.dp)
+            // Keys that cannot follow the pending consonant are dimmed,
+            // and so, more lightly, are keys that lead to no known word
+            .alpha(
+                when {
+                    !key.valid -> 0.4f
+                    probability == 0f -> 0.7f
+                    else -> 1f
+                }
+            )
+            .background(backgroundColor, RoundedCornerShape(8.dp))
+            .onGloballyPositioned { keyBounds = it.boundsInRoot() }
+            .pointerInput(key.key) {
+                detectTapGestures(
+                    onTap = { offset -> onTap(keyBounds.topLeft + offset) },
+                    onLongPress = { onLongClick() }
+                )
+            },
+        contentAlignment = Alignment.Center
     ) {
+        key.secondary?.let {
+            Text(
+                text = it,
+                fontSize = (fontSize / 2).sp,
+                color = textColor,
+                modifier = Modifier
+                    .align(Alignment.TopEnd)
+                    .padding(2.dp)
+            )
+        }
         Text(
-            text = key.label,
+            text = key.primary,
             fontSize = fontSize.sp,
-            fontWeight = FontWeight.Medium,
-            modifier = Modifier.padding(4.dp)
+            // Likely next keys stand out
+            fontWeight = if ((probability ?: 0f) >= LIKELY_KEY) FontWeight.Bold else FontWeight.Medium,
+            color = textColor
         )
     }
 }
-
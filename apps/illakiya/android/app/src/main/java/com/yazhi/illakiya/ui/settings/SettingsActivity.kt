@@ -96,6 +96,13 @@ fun SettingsScreen(
                 onCheckedChange = { prefsManager.setSwipeNedilEnabled(it) }
             )
             
+            SettingsToggle(
+                title = "Incognito",
+                description = "Learn nothing and save nothing you type",
+                checked = settings.incognito,
+                onCheckedChange = { prefsManager.setIncognito(it) }
+            )
+            
             Divider()
             
             // === FEEDBACK SECTION ===
@@ -1,3 +1,6 @@
 fn main() {
-    uniffi::generate_scaffolding("./src/illakiya.udl").unwrap();
+    // The wasm build exports through wasm-bindgen instead (src/wasm.rs)
+    if std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() != Ok("wasm32") {
+        uniffi::generate_scaffolding("./src/illakiya.udl").unwrap();
+    }
 }
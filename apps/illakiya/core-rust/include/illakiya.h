@@ -0,0 +1,235 @@
+#ifndef ILLAKIYA_H
+#define ILLAKIYA_H
+
+/* Generated by cbindgen from src/capi.rs; do not edit. Regenerate with scripts/generate-header.sh */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#define ILLAKIYA_EVENT_KEY_TAP 0
+
+#define ILLAKIYA_EVENT_FLICK 1
+
+#define ILLAKIYA_EVENT_SWIPE 2
+
+#define ILLAKIYA_EVENT_BACKSPACE 3
+
+#define ILLAKIYA_EVENT_SPACE 4
+
+#define ILLAKIYA_EVENT_ENTER 5
+
+#define ILLAKIYA_EVENT_COMMIT_TEXT 6
+
+#define ILLAKIYA_EVENT_SET_LAYER 7
+
+#define ILLAKIYA_EVENT_CLEAR 8
+
+#define ILLAKIYA_LAYER_BASE 0
+
+#define ILLAKIYA_LAYER_NEDIL 1
+
+#define ILLAKIYA_LAYER_GRANTHA 2
+
+#define ILLAKIYA_LAYER_SYMBOLS 3
+
+#define ILLAKIYA_LAYER_NUMBERS 4
+
+#define ILLAKIYA_LAYER_ENGLISH 5
+
+#define ILLAKIYA_FLICK_UP 0
+
+#define ILLAKIYA_FLICK_UP_RIGHT 1
+
+#define ILLAKIYA_FLICK_RIGHT 2
+
+#define ILLAKIYA_FLICK_DOWN_RIGHT 3
+
+#define ILLAKIYA_FLICK_DOWN 4
+
+#define ILLAKIYA_FLICK_DOWN_LEFT 5
+
+#define ILLAKIYA_FLICK_LEFT 6
+
+#define ILLAKIYA_FLICK_UP_LEFT 7
+
+#define ILLAKIYA_BACKSPACE_CHAR 0
+
+#define ILLAKIYA_BACKSPACE_WORD 1
+
+#define ILLAKIYA_INPUT_TEXT 0
+
+#define ILLAKIYA_INPUT_PASSWORD 1
+
+#define ILLAKIYA_INPUT_URL 2
+
+#define ILLAKIYA_INPUT_EMAIL 3
+
+#define ILLAKIYA_INPUT_NUMBER 4
+
+#define ILLAKIYA_INPUT_SEARCH 5
+
+#define ILLAKIYA_INPUT_LONG_FORM 6
+
+// Result of a fallible call
+typedef enum IllakiyaStatus {
+  ILLAKIYA_STATUS_OK = 0,
+  // A required pointer was NULL
+  ILLAKIYA_STATUS_NULL_ARGUMENT,
+  // A string was not valid UTF-8
+  ILLAKIYA_STATUS_INVALID_UTF8,
+  // An enum-valued field was out of range
+  ILLAKIYA_STATUS_INVALID_ARGUMENT,
+  ILLAKIYA_STATUS_LAYOUT_INVALID,
+  ILLAKIYA_STATUS_DICTIONARY_CORRUPT,
+  ILLAKIYA_STATUS_USER_DATA_IO,
+  ILLAKIYA_STATUS_MODEL_LOAD,
+  // The core panicked; the engine is still usable
+  ILLAKIYA_STATUS_PANIC,
+} IllakiyaStatus;
+
+typedef struct IllakiyaEngine IllakiyaEngine;
+
+// A sampled touch point, normalised to 0..1 of the keyboard area
+typedef struct IllakiyaTouchPoint {
+  float x;
+  float y;
+} IllakiyaTouchPoint;
+
+// One input event; which fields are read depends on `kind`
+typedef struct IllakiyaEvent {
+  // `ILLAKIYA_EVENT_*`
+  uint32_t kind;
+  // KEY_TAP, FLICK: key id ("q", "nedil"). COMMIT_TEXT: the text
+  const char *text;
+  // FLICK: `ILLAKIYA_FLICK_*`
+  uint32_t direction;
+  // BACKSPACE: `ILLAKIYA_BACKSPACE_*`
+  uint32_t backspace_mode;
+  // SET_LAYER: `ILLAKIYA_LAYER_*`
+  uint32_t layer;
+  // SET_LAYER: keep the layer after the next key
+  bool locked;
+  // SWIPE: `point_count` points, normalised to the keyboard area
+  const struct IllakiyaTouchPoint *points;
+  size_t point_count;
+} IllakiyaEvent;
+
+// An `EditResult`; apply it in the order documented there. The strings
+// are owned: release them with `illakiya_edit_free`.
+typedef struct IllakiyaEdit {
+  uint32_t delete_before;
+  uint32_t delete_after;
+  char *commit;
+  char *composing;
+  bool nedil_active;
+  bool pending;
+  // `ILLAKIYA_LAYER_*`
+  uint32_t layer;
+} IllakiyaEdit;
+
+// Owned strings; release with `illakiya_string_list_free`
+typedef struct IllakiyaStringList {
+  char **items;
+  size_t len;
+} IllakiyaStringList;
+
+// Called once the dictionary has loaded, on the loading thread
+typedef void (*IllakiyaReadyCallback)(void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Engine with PM0100; the dictionary loads in the background. Returns
+// NULL on failure, with the reason in `status` (which may be NULL).
+struct IllakiyaEngine *illakiya_engine_new(enum IllakiyaStatus *status);
+
+// Engine with a custom layout (same format as pm0100.json)
+struct IllakiyaEngine *illakiya_engine_with_layout(const char *layout_json,
+                                                   enum IllakiyaStatus *status);
+
+void illakiya_engine_free(struct IllakiyaEngine *engine);
+
+bool illakiya_engine_is_dictionary_ready(const struct IllakiyaEngine *engine);
+
+// Call `callback(user_data)` once the dictionary has loaded: at once if
+// it already has, otherwise on the loading thread. `user_data` must stay
+// valid until then.
+enum IllakiyaStatus illakiya_engine_set_ready_callback(const struct IllakiyaEngine *engine,
+                                                       IllakiyaReadyCallback callback,
+                                                       void *user_data);
+
+enum IllakiyaStatus illakiya_engine_process_event(const struct IllakiyaEngine *engine,
+                                                  const struct IllakiyaEvent *event,
+                                                  struct IllakiyaEdit *out);
+
+// A physical key by evdev code; Shift on a letter key is a one-shot
+// nedil. If the layout has no key there, `*handled` is false, `out` holds
+// an empty edit and the host should handle the key itself.
+enum IllakiyaStatus illakiya_engine_process_key_code(const struct IllakiyaEngine *engine,
+                                                     uint32_t code,
+                                                     bool shift,
+                                                     bool *handled,
+                                                     struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_finish_composing(const struct IllakiyaEngine *engine,
+                                                     struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_accept_suggestion(const struct IllakiyaEngine *engine,
+                                                      const char *suggestion,
+                                                      struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_apply_sandhi(const struct IllakiyaEngine *engine,
+                                                 struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_set_surrounding_text(const struct IllakiyaEngine *engine,
+                                                         const char *before,
+                                                         const char *after,
+                                                         struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_undo(const struct IllakiyaEngine *engine,
+                                         struct IllakiyaEdit *out);
+
+enum IllakiyaStatus illakiya_engine_redo(const struct IllakiyaEngine *engine,
+                                         struct IllakiyaEdit *out);
+
+// Up to `limit` words for the composing word, best first
+enum IllakiyaStatus illakiya_engine_get_suggestions(const struct IllakiyaEngine *engine,
+                                                    uint32_t limit,
+                                                    struct IllakiyaStringList *out);
+
+// The sandhi join on offer, or NULL
+char *illakiya_engine_get_sandhi_suggestion(const struct IllakiyaEngine *engine);
+
+// The composing region, or NULL if `engine` is NULL
+char *illakiya_engine_get_composing(const struct IllakiyaEngine *engine);
+
+// `ILLAKIYA_LAYER_*`
+uint32_t illakiya_engine_get_layer(const struct IllakiyaEngine *engine);
+
+// Describe the focused field: `kind` is `ILLAKIYA_INPUT_*`
+enum IllakiyaStatus illakiya_engine_set_input_context(const struct IllakiyaEngine *engine,
+                                                      uint32_t kind,
+                                                      bool no_learning);
+
+void illakiya_engine_reset(const struct IllakiyaEngine *engine);
+
+// Text state and learned data as JSON, for `illakiya_engine_restore`
+char *illakiya_engine_snapshot(const struct IllakiyaEngine *engine);
+
+enum IllakiyaStatus illakiya_engine_restore(const struct IllakiyaEngine *engine, const char *blob);
+
+void illakiya_string_free(char *s);
+
+// Free the strings of `edit` and set them to NULL; the struct itself
+// belongs to the caller
+void illakiya_edit_free(struct IllakiyaEdit *edit);
+
+void illakiya_string_list_free(struct IllakiyaStringList *list);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ILLAKIYA_H */
@@ -0,0 +1,648 @@
+//! C ABI for hosts without UniFFI (Fcitx5, GTK/Qt modules, C/C++ tests).
+//!
+//! The same `KeyboardEngine` the Kotlin IME uses, behind an opaque
+//! `IllakiyaEngine *`. Conventions, declared in `include/illakiya.h`
+//! (generated by cbindgen, see `cbindgen.toml`):
+//!
+//! - Strings are UTF-8 and NUL-terminated. Input strings are borrowed for
+//!   the duration of the call; returned strings belong to the caller and
+//!   are released with `illakiya_string_free`, `illakiya_edit_free` or
+//!   `illakiya_string_list_free`.
+//! - Enum-valued fields are plain `uint32_t` (`ILLAKIYA_EVENT_*`,
+//!   `ILLAKIYA_LAYER_*`, ...), so an out-of-range value from C is reported
+//!   as `ILLAKIYA_STATUS_INVALID_ARGUMENT` rather than being undefined.
+//! - Fallible calls return an `IllakiyaStatus`; no panic crosses the
+//!   boundary.
+//! - Pointers must be valid or NULL; NULL handles and strings are
+//!   rejected with `ILLAKIYA_STATUS_NULL_ARGUMENT`. An engine may be
+//!   used from several threads, but must not be freed while in use.
+
+#![allow(clippy::missing_safety_doc)]
+
+use crate::context::{InputContext, InputKind};
+use crate::edit::EditResult;
+use crate::error::IllakiyaError;
+use crate::event::{BackspaceMode, InputEvent};
+use crate::gesture::TouchPoint;
+use crate::handle::KeyboardEngine;
+use crate::layout::{FlickDirection, Layer};
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Result of a fallible call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllakiyaStatus {
+    Ok = 0,
+    /// A required pointer was NULL
+    NullArgument,
+    /// A string was not valid UTF-8
+    InvalidUtf8,
+    /// An enum-valued field was out of range
+    InvalidArgument,
+    LayoutInvalid,
+    DictionaryCorrupt,
+    UserDataIo,
+    ModelLoad,
+    /// The core panicked; the engine is still usable
+    Panic,
+}
+
+impl From<&IllakiyaError> for IllakiyaStatus {
+    fn from(error: &IllakiyaError) -> Self {
+        match error {
+            IllakiyaError::LayoutInvalid { .. } => IllakiyaStatus::LayoutInvalid,
+            IllakiyaError::DictionaryCorrupt { .. } => IllakiyaStatus::DictionaryCorrupt,
+            IllakiyaError::UserDataIo { .. } => IllakiyaStatus::UserDataIo,
+            IllakiyaError::ModelLoad { .. } => IllakiyaStatus::ModelLoad,
+        }
+    }
+}
+
+pub const ILLAKIYA_EVENT_KEY_TAP: u32 = 0;
+pub const ILLAKIYA_EVENT_FLICK: u32 = 1;
+pub const ILLAKIYA_EVENT_SWIPE: u32 = 2;
+pub const ILLAKIYA_EVENT_BACKSPACE: u32 = 3;
+pub const ILLAKIYA_EVENT_SPACE: u32 = 4;
+pub const ILLAKIYA_EVENT_ENTER: u32 = 5;
+pub const ILLAKIYA_EVENT_COMMIT_TEXT: u32 = 6;
+pub const ILLAKIYA_EVENT_SET_LAYER: u32 = 7;
+pub const ILLAKIYA_EVENT_CLEAR: u32 = 8;
+
+pub const ILLAKIYA_LAYER_BASE: u32 = 0;
+pub const ILLAKIYA_LAYER_NEDIL: u32 = 1;
+pub const ILLAKIYA_LAYER_GRANTHA: u32 = 2;
+pub const ILLAKIYA_LAYER_SYMBOLS: u32 = 3;
+pub const ILLAKIYA_LAYER_NUMBERS: u32 = 4;
+pub const ILLAKIYA_LAYER_ENGLISH: u32 = 5;
+
+pub const ILLAKIYA_FLICK_UP: u32 = 0;
+pub const ILLAKIYA_FLICK_UP_RIGHT: u32 = 1;
+pub const ILLAKIYA_FLICK_RIGHT: u32 = 2;
+pub const ILLAKIYA_FLICK_DOWN_RIGHT: u32 = 3;
+pub const ILLAKIYA_FLICK_DOWN: u32 = 4;
+pub const ILLAKIYA_FLICK_DOWN_LEFT: u32 = 5;
+pub const ILLAKIYA_FLICK_LEFT: u32 = 6;
+pub const ILLAKIYA_FLICK_UP_LEFT: u32 = 7;
+
+pub const ILLAKIYA_BACKSPACE_CHAR: u32 = 0;
+pub const ILLAKIYA_BACKSPACE_WORD: u32 = 1;
+
+pub const ILLAKIYA_INPUT_TEXT: u32 = 0;
+pub const ILLAKIYA_INPUT_PASSWORD: u32 = 1;
+pub const ILLAKIYA_INPUT_URL: u32 = 2;
+pub const ILLAKIYA_INPUT_EMAIL: u32 = 3;
+pub const ILLAKIYA_INPUT_NUMBER: u32 = 4;
+pub const ILLAKIYA_INPUT_SEARCH: u32 = 5;
+pub const ILLAKIYA_INPUT_LONG_FORM: u32 = 6;
+
+const FLICK_DIRECTIONS: [FlickDirection; 8] = [
+    FlickDirection::Up, FlickDirection::UpRight, FlickDirection::Right, FlickDirection::DownRight,
+    FlickDirection::Down, FlickDirection::DownLeft, FlickDirection::Left, FlickDirection::UpLeft,
+];
+
+const INPUT_KINDS: [InputKind; 7] = [
+    InputKind::Text, InputKind::Password, InputKind::Url, InputKind::Email,
+    InputKind::Number, InputKind::Search, InputKind::LongForm,
+];
+
+/// One input event; which fields are read depends on `kind`
+#[repr(C)]
+pub struct IllakiyaEvent {
+    /// `ILLAKIYA_EVENT_*`
+    pub kind: u32,
+    /// KEY_TAP, FLICK: key id ("q", "nedil"). COMMIT_TEXT: the text
+    pub text: *const c_char,
+    /// FLICK: `ILLAKIYA_FLICK_*`
+    pub direction: u32,
+    /// BACKSPACE: `ILLAKIYA_BACKSPACE_*`
+    pub backspace_mode: u32,
+    /// SET_LAYER: `ILLAKIYA_LAYER_*`
+    pub layer: u32,
+    /// SET_LAYER: keep the layer after the next key
+    pub locked: bool,
+    /// SWIPE: `point_count` points, normalised to the keyboard area
+    pub points: *const TouchPoint,
+    pub point_count: usize,
+}
+
+/// An `EditResult`; apply it in the order documented there. The strings
+/// are owned: release them with `illakiya_edit_free`.
+#[repr(C)]
+pub struct IllakiyaEdit {
+    pub delete_before: u32,
+    pub delete_after: u32,
+    pub commit: *mut c_char,
+    pub composing: *mut c_char,
+    pub nedil_active: bool,
+    pub pending: bool,
+    /// `ILLAKIYA_LAYER_*`
+    pub layer: u32,
+}
+
+/// Owned strings; release with `illakiya_string_list_free`
+#[repr(C)]
+pub struct IllakiyaStringList {
+    pub items: *mut *mut c_char,
+    pub len: usize,
+}
+
+/// Called once the dictionary has loaded, on the loading thread
+pub type IllakiyaReadyCallback = extern "C" fn(user_data: *mut c_void);
+
+/// Run `f`, turning a panic into `fallback`
+fn guard<T>(fallback: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+unsafe fn borrow_str<'a>(s: *const c_char) -> Result<&'a str, IllakiyaStatus> {
+    if s.is_null() {
+        return Err(IllakiyaStatus::NullArgument);
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| IllakiyaStatus::InvalidUtf8)
+}
+
+unsafe fn engine_ref<'a>(engine: *const KeyboardEngine) -> Result<&'a KeyboardEngine, IllakiyaStatus> {
+    engine.as_ref().ok_or(IllakiyaStatus::NullArgument)
+}
+
+/// An owned C string. Engine text never contains NUL; drop any that
+/// arrive through committed text rather than fail.
+fn to_c(s: String) -> *mut c_char {
+    CString::new(s.replace('\0', "")).unwrap_or_default().into_raw()
+}
+
+fn layer_code(layer: Layer) -> u32 {
+    Layer::ALL.iter().position(|&l| l == layer).unwrap_or(0) as u32
+}
+
+fn layer_from(code: u32) -> Result<Layer, IllakiyaStatus> {
+    Layer::ALL.get(code as usize).copied().ok_or(IllakiyaStatus::InvalidArgument)
+}
+
+fn edit_to_c(edit: EditResult) -> IllakiyaEdit {
+    IllakiyaEdit {
+        delete_before: edit.delete_before,
+        delete_after: edit.delete_after,
+        commit: to_c(edit.commit),
+        composing: to_c(edit.composing),
+        nedil_active: edit.nedil_active,
+        pending: edit.pending,
+        layer: layer_code(edit.layer),
+    }
+}
+
+unsafe fn event_from(event: &IllakiyaEvent) -> Result<InputEvent, IllakiyaStatus> {
+    let text = || borrow_str(event.text).map(str::to_string);
+    Ok(match event.kind {
+        ILLAKIYA_EVENT_KEY_TAP => InputEvent::KeyTap { key: text()? },
+        ILLAKIYA_EVENT_FLICK => InputEvent::Flick {
+            key: text()?,
+            direction: *FLICK_DIRECTIONS.get(event.direction as usize).ok_or(IllakiyaStatus::InvalidArgument)?,
+        },
+        ILLAKIYA_EVENT_SWIPE => {
+            if event.points.is_null() {
+                return Err(IllakiyaStatus::NullArgument);
+            }
+            InputEvent::Swipe { points: std::slice::from_raw_parts(event.points, event.point_count).to_vec() }
+        }
+        ILLAKIYA_EVENT_BACKSPACE => InputEvent::Backspace {
+            mode: match event.backspace_mode {
+                ILLAKIYA_BACKSPACE_CHAR => BackspaceMode::Char,
+                ILLAKIYA_BACKSPACE_WORD => BackspaceMode::Word,
+                _ => return Err(IllakiyaStatus::InvalidArgument),
+            },
+        },
+        ILLAKIYA_EVENT_SPACE => InputEvent::Space,
+        ILLAKIYA_EVENT_ENTER => InputEvent::Enter,
+        ILLAKIYA_EVENT_COMMIT_TEXT => InputEvent::CommitText { text: text()? },
+        ILLAKIYA_EVENT_SET_LAYER => InputEvent::SetLayer { layer: layer_from(event.layer)?, locked: event.locked },
+        ILLAKIYA_EVENT_CLEAR => InputEvent::Clear,
+        _ => return Err(IllakiyaStatus::InvalidArgument),
+    })
+}
+
+/// Run an edit-returning call and write its result to `out`
+unsafe fn edit_call(
+    engine: *const KeyboardEngine,
+    out: *mut IllakiyaEdit,
+    f: impl FnOnce(&KeyboardEngine) -> Result<EditResult, IllakiyaStatus>,
+) -> IllakiyaStatus {
+    guard(IllakiyaStatus::Panic, || {
+        if out.is_null() {
+            return IllakiyaStatus::NullArgument;
+        }
+        match engine_ref(engine).and_then(f) {
+            Ok(edit) => {
+                out.write(edit_to_c(edit));
+                IllakiyaStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+unsafe fn construct(
+    status: *mut IllakiyaStatus,
+    f: impl FnOnce() -> Result<KeyboardEngine, IllakiyaStatus>,
+) -> *mut KeyboardEngine {
+    let (engine, result) = match guard(Err(IllakiyaStatus::Panic), f) {
+        Ok(engine) => (Box::into_raw(Box::new(engine)), IllakiyaStatus::Ok),
+        Err(e) => (ptr::null_mut(), e),
+    };
+    if let Some(status) = status.as_mut() {
+        *status = result;
+    }
+    engine
+}
+
+/// Engine with PM0100; the dictionary loads in the background. Returns
+/// NULL on failure, with the reason in `status` (which may be NULL).
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_new(status: *mut IllakiyaStatus) -> *mut KeyboardEngine {
+    construct(status, || KeyboardEngine::new().map_err(|e| (&e).into()))
+}
+
+/// Engine with a custom layout (same format as pm0100.json)
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_with_layout(
+    layout_json: *const c_char,
+    status: *mut IllakiyaStatus,
+) -> *mut KeyboardEngine {
+    construct(status, || KeyboardEngine::with_layout(borrow_str(layout_json)?).map_err(|e| (&e).into()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_free(engine: *mut KeyboardEngine) {
+    if !engine.is_null() {
+        drop(Box::from_raw(engine));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_is_dictionary_ready(engine: *const KeyboardEngine) -> bool {
+    guard(false, || engine_ref(engine).is_ok_and(KeyboardEngine::is_dictionary_ready))
+}
+
+/// Call `callback(user_data)` once the dictionary has loaded: at once if
+/// it already has, otherwise on the loading thread. `user_data` must stay
+/// valid until then.
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_set_ready_callback(
+    engine: *const KeyboardEngine,
+    callback: Option<IllakiyaReadyCallback>,
+    user_data: *mut c_void,
+) -> IllakiyaStatus {
+    struct Callback(IllakiyaReadyCallback, *mut c_void);
+    // The host promises `user_data` may be used from the loading thread
+    unsafe impl Send for Callback {}
+    unsafe impl Sync for Callback {}
+    impl crate::resources::ReadyListener for Callback {
+        fn on_ready(&self) {
+            (self.0)(self.1)
+        }
+    }
+
+    guard(IllakiyaStatus::Panic, || {
+        let (engine, callback) = match (engine_ref(engine), callback) {
+            (Ok(engine), Some(callback)) => (engine, callback),
+            _ => return IllakiyaStatus::NullArgument,
+        };
+        engine.set_ready_listener(Box::new(Callback(callback, user_data)));
+        IllakiyaStatus::Ok
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_process_event(
+    engine: *const KeyboardEngine,
+    event: *const IllakiyaEvent,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| {
+        let event = event_from(event.as_ref().ok_or(IllakiyaStatus::NullArgument)?)?;
+        Ok(engine.process_event(event))
+    })
+}
+
+/// A physical key by evdev code; Shift on a letter key is a one-shot
+/// nedil. If the layout has no key there, `*handled` is false, `out` holds
+/// an empty edit and the host should handle the key itself.
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_process_key_code(
+    engine: *const KeyboardEngine,
+    code: u32,
+    shift: bool,
+    handled: *mut bool,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| {
+        let handled = handled.as_mut().ok_or(IllakiyaStatus::NullArgument)?;
+        let edit = engine.process_key_code(code, shift);
+        *handled = edit.is_some();
+        Ok(edit.unwrap_or_else(|| EditResult { layer: engine.get_layer(), ..EditResult::default() }))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_finish_composing(
+    engine: *const KeyboardEngine,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| Ok(engine.finish_composing()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_accept_suggestion(
+    engine: *const KeyboardEngine,
+    suggestion: *const c_char,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| Ok(engine.accept_suggestion(borrow_str(suggestion)?)))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_apply_sandhi(
+    engine: *const KeyboardEngine,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| Ok(engine.apply_sandhi()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_set_surrounding_text(
+    engine: *const KeyboardEngine,
+    before: *const c_char,
+    after: *const c_char,
+    out: *mut IllakiyaEdit,
+) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| {
+        Ok(engine.set_surrounding_text(borrow_str(before)?, borrow_str(after)?))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_undo(engine: *const KeyboardEngine, out: *mut IllakiyaEdit) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| Ok(engine.undo()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_redo(engine: *const KeyboardEngine, out: *mut IllakiyaEdit) -> IllakiyaStatus {
+    edit_call(engine, out, |engine| Ok(engine.redo()))
+}
+
+/// Up to `limit` words for the composing word, best first
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_get_suggestions(
+    engine: *const KeyboardEngine,
+    limit: u32,
+    out: *mut IllakiyaStringList,
+) -> IllakiyaStatus {
+    guard(IllakiyaStatus::Panic, || {
+        if out.is_null() {
+            return IllakiyaStatus::NullArgument;
+        }
+        match engine_ref(engine) {
+            Ok(engine) => {
+                let items: Box<[*mut c_char]> = engine.get_suggestions(limit).into_iter().map(to_c).collect();
+                let len = items.len();
+                out.write(IllakiyaStringList { items: Box::into_raw(items) as *mut *mut c_char, len });
+                IllakiyaStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+/// The sandhi join on offer, or NULL
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_get_sandhi_suggestion(engine: *const KeyboardEngine) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        engine_ref(engine).ok()
+            .and_then(KeyboardEngine::get_sandhi_suggestion)
+            .map_or(ptr::null_mut(), to_c)
+    })
+}
+
+/// The composing region, or NULL if `engine` is NULL
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_get_composing(engine: *const KeyboardEngine) -> *mut c_char {
+    guard(ptr::null_mut(), || engine_ref(engine).map_or(ptr::null_mut(), |e| to_c(e.get_composing())))
+}
+
+/// `ILLAKIYA_LAYER_*`
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_get_layer(engine: *const KeyboardEngine) -> u32 {
+    guard(ILLAKIYA_LAYER_BASE, || engine_ref(engine).map_or(ILLAKIYA_LAYER_BASE, |e| layer_code(e.get_layer())))
+}
+
+/// Describe the focused field: `kind` is `ILLAKIYA_INPUT_*`
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_set_input_context(
+    engine: *const KeyboardEngine,
+    kind: u32,
+    no_learning: bool,
+) -> IllakiyaStatus {
+    guard(IllakiyaStatus::Panic, || {
+        let kind = match INPUT_KINDS.get(kind as usize) {
+            Some(&kind) => kind,
+            None => return IllakiyaStatus::InvalidArgument,
+        };
+        match engine_ref(engine) {
+            Ok(engine) => {
+                engine.set_input_context(InputContext { kind, no_learning });
+                IllakiyaStatus::Ok
+            }
+            Err(status) => status,
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_reset(engine: *const KeyboardEngine) {
+    guard((), || {
+        if let Ok(engine) = engine_ref(engine) {
+            engine.reset();
+        }
+    })
+}
+
+/// Text state and learned data as JSON, for `illakiya_engine_restore`
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_snapshot(engine: *const KeyboardEngine) -> *mut c_char {
+    guard(ptr::null_mut(), || engine_ref(engine).map_or(ptr::null_mut(), |e| to_c(e.snapshot())))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_engine_restore(engine: *const KeyboardEngine, blob: *const c_char) -> IllakiyaStatus {
+    guard(IllakiyaStatus::Panic, || {
+        let restored = engine_ref(engine).and_then(|engine| {
+            engine.restore(borrow_str(blob)?).map_err(|e| (&e).into())
+        });
+        restored.err().unwrap_or(IllakiyaStatus::Ok)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// Free the strings of `edit` and set them to NULL; the struct itself
+/// belongs to the caller
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_edit_free(edit: *mut IllakiyaEdit) {
+    if let Some(edit) = edit.as_mut() {
+        illakiya_string_free(std::mem::replace(&mut edit.commit, ptr::null_mut()));
+        illakiya_string_free(std::mem::replace(&mut edit.composing, ptr::null_mut()));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn illakiya_string_list_free(list: *mut IllakiyaStringList) {
+    if let Some(list) = list.as_mut() {
+        if !list.items.is_null() {
+            let items = Box::from_raw(ptr::slice_from_raw_parts_mut(list.items, list.len));
+            for &item in items.iter() {
+                illakiya_string_free(item);
+            }
+        }
+        list.items = ptr::null_mut();
+        list.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::MaybeUninit;
+
+    fn tap(key: &CStr) -> IllakiyaEvent {
+        IllakiyaEvent {
+            kind: ILLAKIYA_EVENT_KEY_TAP,
+            text: key.as_ptr(),
+            direction: 0,
+            backspace_mode: 0,
+            layer: 0,
+            locked: false,
+            points: ptr::null(),
+            point_count: 0,
+        }
+    }
+
+    /// Process `event`, returning the status and the edit's composing text
+    unsafe fn process(engine: *mut KeyboardEngine, event: &IllakiyaEvent) -> (IllakiyaStatus, String) {
+        let mut edit = MaybeUninit::<IllakiyaEdit>::uninit();
+        let status = illakiya_engine_process_event(engine, event, edit.as_mut_ptr());
+        if status != IllakiyaStatus::Ok {
+            return (status, String::new());
+        }
+        let mut edit = edit.assume_init();
+        let composing = CStr::from_ptr(edit.composing).to_str().unwrap().to_string();
+        illakiya_edit_free(&mut edit);
+        assert!(edit.commit.is_null() && edit.composing.is_null());
+        (status, composing)
+    }
+
+    #[test]
+    fn test_types_through_the_c_abi() {
+        unsafe {
+            let mut status = IllakiyaStatus::Panic;
+            let engine = illakiya_engine_new(&mut status);
+            assert_eq!(status, IllakiyaStatus::Ok);
+            process(engine, &tap(c"u"));
+            assert_eq!(process(engine, &tap(c"z")), (IllakiyaStatus::Ok, "த".to_string()));
+
+            let composing = illakiya_engine_get_composing(engine);
+            assert_eq!(CStr::from_ptr(composing).to_str(), Ok("த"));
+            illakiya_string_free(composing);
+            illakiya_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_physical_keys() {
+        unsafe {
+            let engine = illakiya_engine_new(ptr::null_mut());
+            let mut handled = false;
+            let mut edit = MaybeUninit::<IllakiyaEdit>::uninit();
+            for code in [22, 44] {
+                let status = illakiya_engine_process_key_code(engine, code, false, &mut handled, edit.as_mut_ptr());
+                assert_eq!((status, handled), (IllakiyaStatus::Ok, true));
+                illakiya_edit_free(edit.as_mut_ptr());
+            }
+            // The digit row is left to the host
+            illakiya_engine_process_key_code(engine, 2, false, &mut handled, edit.as_mut_ptr());
+            let mut edit = edit.assume_init();
+            assert!(!handled);
+            assert_eq!(CStr::from_ptr(edit.composing).to_str(), Ok(""));
+            illakiya_edit_free(&mut edit);
+
+            let composing = illakiya_engine_get_composing(engine);
+            assert_eq!(CStr::from_ptr(composing).to_str(), Ok("த"));
+            illakiya_string_free(composing);
+            illakiya_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_suggestions_list() {
+        unsafe {
+            // Same resources as the engine: wait for the word list
+            crate::resources::Resources::pm0100().unwrap();
+            let engine = illakiya_engine_new(ptr::null_mut());
+            for key in [c"u", c"z", c"p", c"x"] {
+                process(engine, &tap(key));
+            }
+            let mut list = MaybeUninit::<IllakiyaStringList>::uninit();
+            assert_eq!(illakiya_engine_get_suggestions(engine, 5, list.as_mut_ptr()), IllakiyaStatus::Ok);
+            let mut list = list.assume_init();
+            assert!(list.len <= 5);
+            let words: Vec<&str> = (0..list.len)
+                .map(|i| CStr::from_ptr(*list.items.add(i)).to_str().unwrap())
+                .collect();
+            assert!(words.contains(&"தமிழ்"));
+            illakiya_string_list_free(&mut list);
+            assert!(list.items.is_null() && list.len == 0);
+            illakiya_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_bad_arguments_are_reported() {
+        unsafe {
+            let mut status = IllakiyaStatus::Ok;
+            assert!(illakiya_engine_with_layout(c"{}".as_ptr(), &mut status).is_null());
+            assert_eq!(status, IllakiyaStatus::LayoutInvalid);
+
+            let engine = illakiya_engine_new(ptr::null_mut());
+            assert_eq!(process(ptr::null_mut(), &tap(c"q")).0, IllakiyaStatus::NullArgument);
+            let mut event = tap(c"q");
+            event.kind = 99;
+            assert_eq!(process(engine, &event).0, IllakiyaStatus::InvalidArgument);
+            event.kind = ILLAKIYA_EVENT_FLICK;
+            event.direction = 8;
+            assert_eq!(process(engine, &event).0, IllakiyaStatus::InvalidArgument);
+            let bad = [0xff_u8, 0];
+            event.kind = ILLAKIYA_EVENT_COMMIT_TEXT;
+            event.text = bad.as_ptr() as *const c_char;
+            assert_eq!(process(engine, &event).0, IllakiyaStatus::InvalidUtf8);
+            assert_eq!(illakiya_engine_restore(engine, c"not json".as_ptr()), IllakiyaStatus::UserDataIo);
+            illakiya_engine_free(engine);
+        }
+    }
+
+    #[test]
+    fn test_header_declares_every_export() {
+        let header = include_str!("../include/illakiya.h");
+        let source = include_str!("capi.rs");
+        for line in source.lines().filter(|l| l.starts_with("pub unsafe extern \"C\" fn ")) {
+            let name = line["pub unsafe extern \"C\" fn ".len()..].split('(').next().unwrap();
+            assert!(header.contains(&format!("{}(", name)), "{} missing from illakiya.h", name);
+        }
+        assert!(header.contains(&format!("ILLAKIYA_EVENT_CLEAR {}", ILLAKIYA_EVENT_CLEAR)));
+    }
+}
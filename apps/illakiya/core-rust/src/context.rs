@@ -0,0 +1,108 @@
+//! Input-field context: what kind of text the focused field expects.
+//!
+//! Hosts describe each field when it gains focus (Android `EditorInfo`,
+//! desktop content-type hints). The engine adjusts learning, suggestions,
+//! sandhi and key mapping accordingly.
+
+/// Kind of text the field accepts
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum InputKind {
+    /// Ordinary short text (chat, forms)
+    #[default]
+    Text,
+    /// Passwords and PINs: nothing is learned, suggested or kept
+    Password,
+    /// Web addresses: keys type Latin characters
+    Url,
+    /// Email addresses: keys type Latin characters
+    Email,
+    /// Numbers: digits, with Tamil numerals offered as suggestions
+    Number,
+    /// Search boxes: suggestions but no sandhi joining
+    Search,
+    /// Long-form prose (notes, documents): full prediction and sandhi
+    LongForm,
+}
+
+/// Descriptor passed by the host when a field gains focus
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InputContext {
+    pub kind: InputKind,
+    /// The field asked not to learn from typing
+    /// (Android `IME_FLAG_NO_PERSONALIZED_LEARNING`)
+    pub no_learning: bool,
+}
+
+impl InputContext {
+    /// Typed words may update dictionary recency and other learned data
+    pub fn learns(&self) -> bool {
+        !self.no_learning && self.kind != InputKind::Password
+    }
+
+    /// Dictionary suggestions are shown
+    pub fn suggests(&self) -> bool {
+        !matches!(self.kind, InputKind::Password | InputKind::Url | InputKind::Email)
+    }
+
+    /// Sandhi joining hints are offered
+    pub fn offers_sandhi(&self) -> bool {
+        matches!(self.kind, InputKind::Text | InputKind::LongForm)
+    }
+
+    /// Keys bypass the PM0100 layout and type their Latin character
+    pub fn latin_passthrough(&self) -> bool {
+        matches!(self.kind, InputKind::Url | InputKind::Email)
+    }
+
+    /// Edits are kept in the undo history
+    pub fn keeps_history(&self) -> bool {
+        self.kind != InputKind::Password
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(kind: InputKind) -> InputContext {
+        InputContext { kind, no_learning: false }
+    }
+
+    #[test]
+    fn test_default_is_plain_text() {
+        let c = InputContext::default();
+        assert_eq!(c.kind, InputKind::Text);
+        assert!(c.learns() && c.suggests() && c.offers_sandhi() && c.keeps_history());
+        assert!(!c.latin_passthrough());
+    }
+
+    #[test]
+    fn test_password_disables_everything() {
+        let c = ctx(InputKind::Password);
+        assert!(!c.learns());
+        assert!(!c.suggests());
+        assert!(!c.offers_sandhi());
+        assert!(!c.keeps_history());
+    }
+
+    #[test]
+    fn test_no_learning_flag() {
+        let c = InputContext { kind: InputKind::LongForm, no_learning: true };
+        assert!(!c.learns());
+        assert!(c.suggests());
+    }
+
+    #[test]
+    fn test_url_and_email_pass_latin_through() {
+        assert!(ctx(InputKind::Url).latin_passthrough());
+        assert!(ctx(InputKind::Email).latin_passthrough());
+        assert!(!ctx(InputKind::Search).latin_passthrough());
+    }
+
+    #[test]
+    fn test_search_has_no_sandhi() {
+        let c = ctx(InputKind::Search);
+        assert!(c.suggests());
+        assert!(!c.offers_sandhi());
+    }
+}
@@ -1,8 +1,17 @@
+use crate::error::IllakiyaError;
+use crate::tamil;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
 
 /// Dictionary engine with Trie-based prefix search for word suggestions.
 /// Loaded from tamil_base.json at init time via include_str!.
+///
+/// The word list and trie (`Lexicon`) are immutable and shared behind an
+/// `Arc`; each `Dictionary` only owns its recency list and learning flag.
+/// The lexicon may still be loading (`LexiconCell`), in which case the
+/// dictionary behaves as empty.
 
 #[derive(Debug, Deserialize)]
 struct DictFile {
@@ -29,38 +38,52 @@ struct TrieNode {
     children: HashMap<char, TrieNode>,
     entries: Vec<usize>, // Indices into the word list
     is_word: bool,
+    /// Total frequency of the words in this subtree
+    weight: u64,
 }
 
-pub struct Dictionary {
+/// Immutable word list and prefix trie
+#[derive(Debug)]
+pub struct Lexicon {
     entries: Vec<DictEntry>,
     trie: TrieNode,
-    /// Recent words for frequency boosting
-    recents: Vec<String>,
-    max_recents: usize,
 }
 
-impl Dictionary {
-    pub fn new() -> Self {
-        let json = include_str!("../../data/dictionary/tamil_base.json");
+impl Lexicon {
+    /// The embedded base word list, parsed once per process
+    pub fn base() -> Result<Arc<Self>, IllakiyaError> {
+        static BASE: OnceLock<Result<Arc<Lexicon>, IllakiyaError>> = OnceLock::new();
+        BASE.get_or_init(|| {
+            Self::from_json(include_str!("../../data/dictionary/tamil_base.json")).map(Arc::new)
+        }).clone()
+    }
+
+    /// Parse a word list in the `tamil_base.json` format
+    pub fn from_json(json: &str) -> Result<Self, IllakiyaError> {
         let dict_file: DictFile = serde_json::from_str(json)
-            .expect("Invalid tamil_base.json");
-        
-        let mut dict = Self {
-            entries: dict_file.words,
-            trie: TrieNode::default(),
-            recents: Vec::new(),
-            max_recents: 50,
-        };
-        dict.build_trie();
-        dict
+            .map_err(|e| IllakiyaError::DictionaryCorrupt { message: e.to_string() })?;
+        Ok(Self::with_entries(dict_file.words))
+    }
+
+    /// A lexicon with no words
+    pub fn empty() -> Self {
+        Self::with_entries(Vec::new())
+    }
+
+    fn with_entries(entries: Vec<DictEntry>) -> Self {
+        let mut lexicon = Self { entries, trie: TrieNode::default() };
+        lexicon.build_trie();
+        lexicon
     }
 
     /// Build the prefix trie from all dictionary entries
     fn build_trie(&mut self) {
         for (idx, entry) in self.entries.iter().enumerate() {
             let mut node = &mut self.trie;
+            node.weight += entry.freq as u64;
             for ch in entry.tamil.chars() {
                 node = node.children.entry(ch).or_default();
+                node.weight += entry.freq as u64;
             }
             node.is_word = true;
             node.entries.push(idx);
@@ -69,13 +92,7 @@ impl Dictionary {
 
     /// Look up an exact word. Returns entry if found.
     pub fn lookup(&self, word: &str) -> Option<&DictEntry> {
-        let mut node = &self.trie;
-        for ch in word.chars() {
-            match node.children.get(&ch) {
-                Some(next) => node = next,
-                None => return None,
-            }
-        }
+        let node = self.node(word)?;
         if node.is_word {
             node.entries.first().map(|&idx| &self.entries[idx])
         } else {
@@ -83,6 +100,148 @@ impl Dictionary {
         }
     }
 
+    /// Trie node reached by `prefix`
+    fn node(&self, prefix: &str) -> Option<&TrieNode> {
+        prefix.chars().try_fold(&self.trie, |node, ch| node.children.get(&ch))
+    }
+
+    /// Total frequency of the words starting with `prefix`
+    pub fn prefix_weight(&self, prefix: &str) -> u64 {
+        self.node(prefix).map_or(0, |node| node.weight)
+    }
+
+    /// Like `prefix_weight`, but `prefix` must end on a whole letter:
+    /// continuations starting with a vowel sign or pulli are not counted,
+    /// so "க" covers கடல் but not கா or க்க.
+    pub fn letter_weight(&self, prefix: &str) -> u64 {
+        let Some(node) = self.node(prefix) else { return 0 };
+        let own: u64 = node.entries.iter().map(|&idx| self.entries[idx].freq as u64).sum();
+        let rest: u64 = node.children.iter()
+            .filter(|(&ch, _)| !tamil::is_dependent_sign(ch))
+            .map(|(_, child)| child.weight)
+            .sum();
+        own + rest
+    }
+
+    /// All words starting with `prefix`, with their base frequency
+    fn completions(&self, prefix: &str) -> Vec<(String, u32)> {
+        let mut candidates = Vec::new();
+        if let Some(node) = self.node(prefix) {
+            self.collect_words(node, &mut candidates);
+        }
+        candidates
+    }
+
+    /// Recursively collect all words from a trie node
+    fn collect_words(&self, node: &TrieNode, results: &mut Vec<(String, u32)>) {
+        for &idx in &node.entries {
+            let entry = &self.entries[idx];
+            results.push((entry.tamil.clone(), entry.freq));
+        }
+        for child in node.children.values() {
+            self.collect_words(child, results);
+        }
+    }
+
+    /// All words with their base frequency
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.entries.iter().map(|e| (e.tamil.as_str(), e.freq))
+    }
+
+    pub fn word_count(&self) -> u32 {
+        self.entries.len() as u32
+    }
+}
+
+/// Slot for a lexicon loaded in the background, shared by every
+/// dictionary over it. Filled once.
+#[derive(Default)]
+pub struct LexiconCell(OnceLock<Arc<Lexicon>>);
+
+impl LexiconCell {
+    pub fn ready(lexicon: Arc<Lexicon>) -> Self {
+        Self(OnceLock::from(lexicon))
+    }
+
+    pub fn get(&self) -> Option<&Arc<Lexicon>> {
+        self.0.get()
+    }
+
+    /// Fill the slot; ignored if it is already filled
+    pub fn set(&self, lexicon: Arc<Lexicon>) {
+        let _ = self.0.set(lexicon);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.get().is_some()
+    }
+}
+
+pub struct Dictionary {
+    lexicon: Arc<LexiconCell>,
+    /// Recent words for frequency boosting
+    recents: RwLock<Vec<String>>,
+    max_recents: usize,
+    /// When false, typing leaves no trace (incognito)
+    learning_enabled: AtomicBool,
+}
+
+impl Dictionary {
+    /// Dictionary over the embedded base word list
+    pub fn new() -> Result<Self, IllakiyaError> {
+        Ok(Self::with_lexicon(Lexicon::base()?))
+    }
+
+    /// Parse a dictionary in the `tamil_base.json` format
+    pub fn from_json(json: &str) -> Result<Self, IllakiyaError> {
+        Ok(Self::with_lexicon(Arc::new(Lexicon::from_json(json)?)))
+    }
+
+    /// A dictionary with no words, for running without one
+    pub fn empty() -> Self {
+        Self::with_lexicon(Arc::new(Lexicon::empty()))
+    }
+
+    /// Dictionary with its own recency list over a shared word list
+    pub fn with_lexicon(lexicon: Arc<Lexicon>) -> Self {
+        Self::shared(Arc::new(LexiconCell::ready(lexicon)))
+    }
+
+    /// Dictionary over a word list that may still be loading
+    pub fn shared(lexicon: Arc<LexiconCell>) -> Self {
+        Self {
+            lexicon,
+            recents: RwLock::new(Vec::new()),
+            max_recents: 50,
+            learning_enabled: AtomicBool::new(true),
+        }
+    }
+
+    /// The word list, or None while it is loading
+    pub fn lexicon(&self) -> Option<&Arc<Lexicon>> {
+        self.lexicon.get()
+    }
+
+    /// False until the word list has loaded
+    pub fn is_ready(&self) -> bool {
+        self.lexicon.is_ready()
+    }
+
+    /// Look up an exact word. Returns entry if found.
+    pub fn lookup(&self, word: &str) -> Option<&DictEntry> {
+        self.lexicon()?.lookup(word)
+    }
+
+    /// Total frequency of the words starting with `prefix`
+    pub fn prefix_weight(&self, prefix: &str) -> u64 {
+        self.lexicon().map_or(0, |l| l.prefix_weight(prefix))
+    }
+
+    /// See `Lexicon::letter_weight`
+    pub fn letter_weight(&self, prefix: &str) -> u64 {
+        self.lexicon().map_or(0, |l| l.letter_weight(prefix))
+    }
+
     /// Check if a word exists in the dictionary
     pub fn contains(&self, word: &str) -> bool {
         self.lookup(word).is_some()
@@ -91,24 +250,15 @@ impl Dictionary {
     /// Prefix search: find all words starting with the given prefix.
     /// Returns up to `limit` results, sorted by frequency (descending).
     pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<String> {
-        if prefix.is_empty() { return Vec::new(); }
-
-        // Navigate to prefix node
-        let mut node = &self.trie;
-        for ch in prefix.chars() {
-            match node.children.get(&ch) {
-                Some(next) => node = next,
-                None => return Vec::new(),
-            }
-        }
-
-        // Collect all words under this node
-        let mut candidates: Vec<(String, u32)> = Vec::new();
-        self.collect_words(node, &mut candidates);
+        let Some(lexicon) = self.lexicon().filter(|_| !prefix.is_empty()) else {
+            return Vec::new();
+        };
+        let mut candidates = lexicon.completions(prefix);
 
         // Boost recent words
+        let recents = self.recents.read().unwrap_or_else(PoisonError::into_inner);
         for (word, freq) in candidates.iter_mut() {
-            if self.recents.contains(word) {
+            if recents.contains(word) {
                 *freq += 50; // Recency boost
             }
         }
@@ -119,32 +269,42 @@ impl Dictionary {
         candidates.into_iter().map(|(w, _)| w).collect()
     }
 
-    /// Recursively collect all words from a trie node
-    fn collect_words(&self, node: &TrieNode, results: &mut Vec<(String, u32)>) {
-        for &idx in &node.entries {
-            let entry = &self.entries[idx];
-            results.push((entry.tamil.clone(), entry.freq));
-        }
-        for child in node.children.values() {
-            self.collect_words(child, results);
-        }
-    }
-
     /// Record that a word was typed (for recency boosting)
-    pub fn record_usage(&mut self, word: &str) {
+    pub fn record_usage(&self, word: &str) {
+        if !self.learning_enabled.load(Ordering::Relaxed) { return; }
+        let mut recents = self.recents.write().unwrap_or_else(PoisonError::into_inner);
         // Remove if already in recents
-        self.recents.retain(|w| w != word);
+        recents.retain(|w| w != word);
         // Push to front
-        self.recents.insert(0, word.to_string());
+        recents.insert(0, word.to_string());
         // Trim
-        if self.recents.len() > self.max_recents {
-            self.recents.pop();
-        }
+        recents.truncate(self.max_recents);
+    }
+
+    /// Enable or disable learning from typed words
+    pub fn set_learning_enabled(&self, enabled: bool) {
+        self.learning_enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Recently used words, most recent first
+    pub fn recents(&self) -> Vec<String> {
+        self.recents.read().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Replace the recency list (e.g. when restoring a snapshot)
+    pub fn set_recents(&self, mut recents: Vec<String>) {
+        recents.truncate(self.max_recents);
+        *self.recents.write().unwrap_or_else(PoisonError::into_inner) = recents;
+    }
+
+    /// All words with their base frequency
+    pub fn words(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.lexicon().into_iter().flat_map(|l| l.words())
     }
 
     /// Get word count
     pub fn word_count(&self) -> u32 {
-        self.entries.len() as u32
+        self.lexicon().map_or(0, |l| l.word_count())
     }
 
     /// Get translation for a Tamil word
@@ -164,28 +324,46 @@ mod tests {
 
     #[test]
     fn test_load_dictionary() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         assert!(dict.word_count() > 100);
     }
 
     #[test]
     fn test_exact_lookup() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         let entry = dict.lookup("தமிழ்");
         assert!(entry.is_some());
         assert_eq!(entry.unwrap().en, "Tamil");
     }
 
+    #[test]
+    fn test_prefix_weight() {
+        let dict = Dictionary::new().unwrap();
+        let tamil = dict.prefix_weight("தமி");
+        assert!(tamil >= 95 + 80); // தமிழ், தமிழன்
+        assert!(dict.prefix_weight("தம") >= tamil);
+        assert_eq!(dict.prefix_weight("ஞௌ"), 0);
+    }
+
+    #[test]
+    fn test_letter_weight_excludes_vowel_signs() {
+        let dict = Dictionary::new().unwrap();
+        // "நா" continues with ன், ம், ... but "ந" alone also covers நா
+        assert!(dict.letter_weight("ந") < dict.prefix_weight("ந"));
+        // A vowel sign is never followed by another sign
+        assert_eq!(dict.letter_weight("நா"), dict.prefix_weight("நா"));
+    }
+
     #[test]
     fn test_contains() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         assert!(dict.contains("நான்"));
         assert!(!dict.contains("xyzzyx"));
     }
 
     #[test]
     fn test_prefix_suggest() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         let suggestions = dict.suggest("தமி", 5);
         assert!(!suggestions.is_empty());
         assert!(suggestions.iter().any(|s| s == "தமிழ்"));
@@ -193,21 +371,21 @@ mod tests {
 
     #[test]
     fn test_empty_prefix() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         let suggestions = dict.suggest("", 5);
         assert!(suggestions.is_empty());
     }
 
     #[test]
     fn test_no_match() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         let suggestions = dict.suggest("zzz", 5);
         assert!(suggestions.is_empty());
     }
 
     #[test]
     fn test_recency_boost() {
-        let mut dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         let before = dict.suggest("வ", 3);
         dict.record_usage("வணக்கம்");
         let after = dict.suggest("வ", 3);
@@ -218,15 +396,52 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_learning_disabled_records_nothing() {
+        let dict = Dictionary::new().unwrap();
+        dict.set_learning_enabled(false);
+        dict.record_usage("வணக்கம்");
+        assert!(dict.recents().is_empty());
+    }
+
+    #[test]
+    fn test_dictionaries_share_base_lexicon() {
+        let a = Dictionary::new().unwrap();
+        let b = Dictionary::new().unwrap();
+        assert!(Arc::ptr_eq(a.lexicon().unwrap(), b.lexicon().unwrap()));
+        a.record_usage("வணக்கம்");
+        assert!(b.recents().is_empty());
+    }
+
+    #[test]
+    fn test_loading_dictionary_is_empty_until_filled() {
+        let cell = Arc::new(LexiconCell::default());
+        let dict = Dictionary::shared(cell.clone());
+        assert!(!dict.is_ready());
+        assert!(dict.suggest("த", 5).is_empty());
+        assert_eq!(dict.prefix_weight(""), 0);
+        cell.set(Lexicon::base().unwrap());
+        assert!(dict.is_ready());
+        assert!(!dict.suggest("த", 5).is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_and_empty_dictionary() {
+        assert!(matches!(Dictionary::from_json("[]"), Err(IllakiyaError::DictionaryCorrupt { .. })));
+        let dict = Dictionary::empty();
+        assert_eq!(dict.word_count(), 0);
+        assert!(dict.suggest("த", 5).is_empty());
+    }
+
     #[test]
     fn test_translate() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         assert_eq!(dict.translate("நான்"), Some("I".to_string()));
     }
 
     #[test]
     fn test_transliterate() {
-        let dict = Dictionary::new();
+        let dict = Dictionary::new().unwrap();
         assert_eq!(dict.transliterate("நான்"), Some("naan".to_string()));
     }
 }
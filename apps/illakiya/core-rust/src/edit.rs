@@ -0,0 +1,80 @@
+use crate::layout::Layer;
+
+/// Structured edit returned by the engine for every input operation.
+///
+/// Hosts apply an edit in this order:
+///   1. Drop the current composing (underlined) region
+///   2. Delete `delete_before` characters before and `delete_after`
+///      characters after the cursor
+///   3. Insert `commit` as final text
+///   4. Set `composing` as the new composing region (may be empty)
+///
+/// This maps directly onto Android's `deleteSurroundingText`,
+/// `commitText` and `setComposingText`, and onto desktop IME
+/// commit/preedit signals. Lengths are counted in Unicode scalar values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditResult {
+    /// Characters to delete before the cursor
+    pub delete_before: u32,
+    /// Characters to delete after the cursor
+    pub delete_after: u32,
+    /// Text to commit at the cursor
+    pub commit: String,
+    /// Text to show as the composing region after the commit
+    pub composing: String,
+    /// Long vowel modifier is armed for the next vowel
+    pub nedil_active: bool,
+    /// A consonant is waiting for its vowel
+    pub pending: bool,
+    /// Key layer after this edit; hosts re-render labels when it changes
+    pub layer: Layer,
+}
+
+impl EditResult {
+    /// Merge `next` into this edit, as if the host applied both in turn
+    pub fn then(self, next: EditResult) -> EditResult {
+        let mut commit = self.commit;
+        let mut delete_before = self.delete_before;
+        let committed = commit.chars().count() as u32;
+        if next.delete_before <= committed {
+            let keep = (committed - next.delete_before) as usize;
+            commit = commit.chars().take(keep).collect();
+        } else {
+            delete_before += next.delete_before - committed;
+            commit.clear();
+        }
+        commit.push_str(&next.commit);
+        EditResult {
+            delete_before,
+            delete_after: self.delete_after + next.delete_after,
+            commit,
+            ..next
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(delete_before: u32, commit: &str, composing: &str) -> EditResult {
+        EditResult {
+            delete_before,
+            commit: commit.to_string(),
+            composing: composing.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_then_appends_commits() {
+        let merged = edit(2, "நான் ", "").then(edit(0, "", "த"));
+        assert_eq!(merged, edit(2, "நான் ", "த"));
+    }
+
+    #[test]
+    fn test_then_deletes_into_previous_commit() {
+        assert_eq!(edit(0, "ab", "").then(edit(1, "c", "")), edit(0, "ac", ""));
+        assert_eq!(edit(1, "a", "").then(edit(3, "", "x")), edit(3, "", "x"));
+    }
+}
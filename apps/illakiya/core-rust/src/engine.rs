@@ -1,20 +1,52 @@
-use crate::layout::LayoutDef;
+use crate::layout::{FlickDirection, KeyboardModel, Layer};
 use crate::dictionary::Dictionary;
+use crate::error::IllakiyaError;
 use crate::sandhi::AdhanSandhi;
+use crate::edit::EditResult;
+use crate::history::{EditOp, TextState};
+use crate::snapshot::{EngineSnapshot, SessionSnapshot, SNAPSHOT_VERSION};
+use crate::session::{Session, DEFAULT_SESSION};
+use crate::context::{InputContext, InputKind};
+use crate::hints;
+use crate::touch::{self, KeyCandidate, TapRecord};
+use crate::event::{BackspaceMode, InputEvent};
+use crate::gesture::{GestureRecord, TouchPoint};
+use crate::resources::Resources;
+use crate::keymap::Keymap;
+use crate::numerals;
+use crate::tamil;
+use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Core keyboard state machine.
 /// Integrates layout, dictionary, and sandhi into a unified engine.
-pub struct KeyboardEngine {
-    layout: LayoutDef,
+/// Single-threaded (`&mut self`); `handle::KeyboardEngine` shares it
+/// across threads.
+pub struct Engine {
+    /// Layout, word list and glide templates, shared between engines
+    resources: Arc<Resources>,
+    /// Recency over the shared word list
     dict: Dictionary,
     sandhi: AdhanSandhi,
-    buffer: String,
-    pending_consonant: Option<String>,
-    nedil_active: bool,
-    /// Word boundaries for sandhi detection
-    words: Vec<String>,
-    /// Current word being typed
-    current_word: String,
+    /// State of the focused input field
+    session: Session,
+    active_session: String,
+    /// Sessions of other input fields, kept until the host destroys them
+    sessions: HashMap<String, Session>,
+    /// Incognito: no learning, and snapshots carry no typed text
+    incognito: bool,
+}
+
+/// Words kept from a glide: the typed one and its alternatives
+const GESTURE_CANDIDATES: usize = 5;
+
+/// Hosts pass at least this much text before the cursor when the field
+/// has it (Android sends 64 characters)
+const SURROUNDING_CONTEXT: usize = 64;
+
+/// Characters that belong to a word; anything else is a commit point
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || ('\u{0B80}'..='\u{0BFF}').contains(&c)
 }
 
 /// Suggestion from the engine (word + source)
@@ -31,169 +63,534 @@ pub enum SuggestionSource {
     Recent,
 }
 
-impl KeyboardEngine {
-    pub fn new() -> Self {
+impl Engine {
+    /// Engine with the embedded PM0100 layout and base dictionary,
+    /// loaded before returning
+    pub fn new() -> Result<Self, IllakiyaError> {
+        Ok(Self::with_resources(Resources::pm0100()?))
+    }
+
+    /// Engine with a custom layout definition (same format as pm0100.json)
+    pub fn with_layout(layout_json: &str) -> Result<Self, IllakiyaError> {
+        let resources = Resources::with_layout(layout_json)?;
+        resources.load_language();
+        Ok(Self::with_resources(resources))
+    }
+
+    /// Engine over `resources`, which may still be loading their word list
+    pub fn with_resources(resources: Arc<Resources>) -> Self {
         Self {
-            layout: LayoutDef::load_pm0100(),
-            dict: Dictionary::new(),
+            dict: Dictionary::shared(resources.lexicon.clone()),
+            resources,
             sandhi: AdhanSandhi::new(),
-            buffer: String::new(),
-            pending_consonant: None,
-            nedil_active: false,
-            words: Vec::new(),
-            current_word: String::new(),
+            session: Session::default(),
+            active_session: DEFAULT_SESSION.to_string(),
+            sessions: HashMap::new(),
+            incognito: false,
         }
     }
 
     /// Toggle long vowel mode (triggered by swipe up)
     pub fn toggle_nedil(&mut self) {
-        self.nedil_active = !self.nedil_active;
+        self.toggle_layer(Layer::Nedil);
+    }
+
+    /// Toggle grantha mode (triggered by long press)
+    pub fn toggle_grantha(&mut self) {
+        self.toggle_layer(Layer::Grantha);
+    }
+
+    /// Switch `layer` on (one-shot or locked, per layer) or back to base
+    fn toggle_layer(&mut self, layer: Layer) {
+        self.session.history.disarm();
+        if self.session.layer == layer {
+            self.session.set_layer(Layer::Base, false);
+        } else {
+            self.session.set_layer(layer, !layer.is_one_shot());
+        }
+    }
+
+    /// Shift key press. A one-shot shift pressed twice locks; pressing
+    /// the shift of a locked layer returns to base.
+    fn press_shift(&mut self, layer: Layer) {
+        let session = &mut self.session;
+        if session.layer != layer {
+            session.set_layer(layer, !layer.is_one_shot());
+        } else if session.layer_locked {
+            session.set_layer(Layer::Base, false);
+        } else {
+            session.set_layer(layer, true);
+        }
+    }
+
+    /// Switch to `layer` explicitly; `locked` keeps it on after a key
+    pub fn set_layer(&mut self, layer: Layer, locked: bool) {
+        self.session.history.disarm();
+        self.session.set_layer(layer, locked);
+    }
+
+    pub fn get_layer(&self) -> Layer {
+        self.session.layer
+    }
+
+    pub fn is_layer_locked(&self) -> bool {
+        self.session.layer_locked
+    }
+
+    /// Key → label map for the active layer, so hosts render labels
+    /// from the layout instead of duplicating it
+    pub fn get_key_labels(&self) -> HashMap<String, String> {
+        if self.session.context.latin_passthrough() {
+            return self.resources.layout.labels(Layer::English);
+        }
+        self.resources.layout.labels(self.session.layer)
+    }
+
+    /// Renderable keyboard for the active layer, with keys that cannot
+    /// follow the pending consonant marked invalid
+    pub fn get_keyboard_model(&self) -> KeyboardModel {
+        let layer = if self.session.context.latin_passthrough() {
+            Layer::English
+        } else {
+            self.session.layer
+        };
+        let pending = self.session.pending_consonant.as_deref();
+        self.resources.layout.keyboard_model(layer, self.session.layer_locked, pending)
+    }
+
+    /// Process a single input event.
+    /// Returns the edit the host should apply to its text field.
+    pub fn process_event(&mut self, event: InputEvent) -> EditResult {
+        // Only a backspace straight after an autocorrection reverts it
+        if !matches!(event, InputEvent::Backspace { .. }) {
+            self.session.history.disarm();
+        }
+        match event {
+            InputEvent::KeyTap { key } => self.typed(EditOp::Typing, |eng| eng.process_key(&key)),
+            InputEvent::Flick { key, direction } => self.process_flick(&key, direction),
+            InputEvent::Swipe { points } => self.process_gesture(points),
+            InputEvent::Backspace { mode } => self.backspace(mode),
+            InputEvent::Space => self.typed(EditOp::Typing, |eng| eng.commit_word_with(" ")),
+            InputEvent::Enter => self.typed(EditOp::Typing, |eng| eng.commit_word_with("\n")),
+            InputEvent::CommitText { text } => self.typed(EditOp::Typing, |eng| eng.commit_word_with(&text)),
+            InputEvent::SetLayer { layer, locked } => {
+                self.set_layer(layer, locked);
+                self.edit(0, String::new())
+            }
+            InputEvent::Clear => self.typed(EditOp::Clear, |eng| {
+                eng.session.clear_text();
+                String::new()
+            }),
+        }
+    }
+
+    /// Process a key code ("q", "space", "backspace", "clear", ...).
+    /// Compatibility shim over `process_event`.
+    pub fn process_input(&mut self, key: &str) -> EditResult {
+        self.process_event(InputEvent::from_key(key))
+    }
+
+    /// Apply a text change and record it for undo. `change` returns the
+    /// committed text; a commit ends the current typing run.
+    fn typed(&mut self, op: EditOp, change: impl FnOnce(&mut Self) -> String) -> EditResult {
+        let before = self.text_state();
+        let commit = change(self);
+        let edit = self.edit(0, commit);
+        self.record(op, before);
+        if !edit.commit.is_empty() {
+            self.session.history.end_run();
+        }
+        edit
+    }
+
+    fn backspace(&mut self, mode: BackspaceMode) -> EditResult {
+        // Backspace right after an autocorrection reverts it
+        if self.session.history.autocorrect_armed() {
+            return self.undo();
+        }
+        let before = self.text_state();
+        let edit = match mode {
+            BackspaceMode::Char => self.handle_backspace(),
+            BackspaceMode::Word => self.handle_backspace_word(),
+        };
+        self.record(EditOp::Typing, before);
+        edit
+    }
+
+    /// Flick a consonant key: types the uyirmei with the vowel the layout
+    /// assigns to `direction` (lengthened under the nedil shift). Keys
+    /// that are not consonants on the active layer behave as a tap.
+    pub fn process_flick(&mut self, key: &str, direction: FlickDirection) -> EditResult {
+        self.session.history.disarm();
+        let Some((consonant, vowel)) = self.flick_letter(key, direction) else {
+            return self.process_event(InputEvent::from_key(key));
+        };
+        let before = self.text_state();
+        self.session.release_one_shot();
+        self.handle_consonant(&consonant);
+        self.handle_vowel(&vowel);
+        self.record(EditOp::Typing, before);
+        self.edit(0, String::new())
+    }
+
+    /// Consonant and vowel typed by a flick, if `key` takes flicks
+    fn flick_letter(&self, key: &str, direction: FlickDirection) -> Option<(String, String)> {
+        if self.session.context.latin_passthrough() {
+            return None;
+        }
+        let layer = self.session.layer;
+        let consonant = match layer {
+            Layer::Base | Layer::Nedil => self.resources.layout.base_lookup(key),
+            Layer::Grantha => self.resources.layout.layer_lookup(layer, key),
+            _ => None,
+        }.filter(|c| tamil::ends_with_pulli(c))?;
+        let vowel = self.resources.layout.flick_vowel(key, direction, layer == Layer::Nedil)?;
+        Some((consonant.clone(), vowel.clone()))
+    }
+
+    /// A key pressed on a physical keyboard, by evdev code. Shift on a
+    /// letter key arms the nedil layer for it. None if the layout has no
+    /// key there: the host should handle the key itself.
+    pub fn process_key_code(&mut self, code: u32, shift: bool) -> Option<EditResult> {
+        let key = self.resources.keymap.key(code)?.to_string();
+        if shift && Keymap::is_letter(&key) && self.session.layer == Layer::Base {
+            self.set_layer(Layer::Nedil, false);
+        }
+        Some(self.process_event(InputEvent::from_key(&key)))
+    }
+
+    /// Type the key most likely meant by a tap at (`x`, `y`), normalised
+    /// to 0..1 of the keyboard area. The touch model is weighted by the
+    /// dictionary; alternatives are kept for `correct_last_tap`.
+    pub fn process_tap(&mut self, x: f32, y: f32) -> EditResult {
+        let touch = touch::touch_distribution(&self.resources.layout, x, y);
+        self.process_touch(touch)
+    }
+
+    /// `process_tap` for hosts with their own touch model: `candidates`
+    /// holds a likelihood per key code.
+    pub fn process_touch(&mut self, candidates: Vec<KeyCandidate>) -> EditResult {
+        let ranked = touch::decode(&candidates, &self.get_key_probabilities());
+        let Some(best) = ranked.first() else {
+            return self.edit(0, String::new());
+        };
+        let key = best.key.clone();
+        let before = self.text_state();
+        let (layer, layer_locked) = (self.session.layer, self.session.layer_locked);
+        let edit = self.process_event(InputEvent::from_key(&key));
+        self.session.last_tap = Some(TapRecord {
+            before,
+            layer,
+            layer_locked,
+            after: self.text_state(),
+            alternatives: ranked,
+        });
+        edit
+    }
+
+    /// Ranked candidates for the last tap, the typed key first.
+    /// Empty once anything else has changed the text.
+    pub fn get_tap_alternatives(&self) -> Vec<KeyCandidate> {
+        match &self.session.last_tap {
+            Some(tap) if !self.session.differs_from(&tap.after) => tap.alternatives.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Replace the key typed by the last tap with `key`
+    pub fn correct_last_tap(&mut self, key: &str) -> EditResult {
+        let tap = match self.session.last_tap.take() {
+            Some(tap) if !self.session.differs_from(&tap.after) => tap,
+            _ => return self.edit(0, String::new()),
+        };
+        let revert = self.restore_text_state(tap.before);
+        self.session.set_layer(tap.layer, tap.layer_locked);
+        revert.then(self.process_event(InputEvent::from_key(key)))
     }
 
-    /// Process a single key press.
-    /// Returns the text to commit.
-    pub fn process_input(&mut self, key: &str) -> String {
-        // 1. Check vowels (short, long, or special)
-        let vowel = self.layout.any_vowel_lookup(key, self.nedil_active).cloned();
-        if self.nedil_active { self.nedil_active = false; }
+    /// Words matching a glide through `points` (normalised to 0..1 of the
+    /// keyboard area), best first
+    pub fn decode_gesture(&self, points: Vec<TouchPoint>, limit: u32) -> Vec<String> {
+        self.resources.gestures().map_or_else(Vec::new, |g| g.decode(&points, limit as usize))
+    }
+
+    /// Type the word best matching a glide. A word being composed is
+    /// committed first with a space; the glided word stays composing so
+    /// `accept_suggestion` can swap in an alternative.
+    pub fn process_gesture(&mut self, points: Vec<TouchPoint>) -> EditResult {
+        self.session.history.disarm();
+        let latin = self.session.layer == Layer::English || self.session.context.latin_passthrough();
+        let candidates = match self.resources.gestures() {
+            Some(gestures) if !latin => gestures.decode(&points, GESTURE_CANDIDATES),
+            _ => Vec::new(),
+        };
+        let Some(best) = candidates.first().cloned() else {
+            return self.edit(0, String::new());
+        };
+
+        let before = self.text_state();
+        let commit = if self.session.current_word.is_empty() && self.session.pending_consonant.is_none() {
+            String::new()
+        } else {
+            self.commit_word_with(" ")
+        };
+        self.session.set_layer(Layer::Base, false);
+        self.session.current_word = best;
+
+        // A glide is one undo step of its own
+        self.session.history.end_run();
+        self.record(EditOp::Typing, before);
+        self.session.history.end_run();
+        self.session.last_gesture = Some(GestureRecord { after: self.text_state(), candidates });
+        self.edit(0, commit)
+    }
+
+    /// Ranked words for the last glide, the typed one first.
+    /// Empty once anything else has changed the text.
+    pub fn get_gesture_alternatives(&self) -> Vec<String> {
+        match &self.session.last_gesture {
+            Some(gesture) if !self.session.differs_from(&gesture.after) => gesture.candidates.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Build an edit carrying the current composing text and state flags
+    fn edit(&self, delete_before: u32, commit: String) -> EditResult {
+        EditResult {
+            delete_before,
+            delete_after: 0,
+            commit,
+            composing: self.get_composing(),
+            nedil_active: self.session.layer == Layer::Nedil,
+            pending: self.session.pending_consonant.is_some(),
+            layer: self.session.layer,
+        }
+    }
+
+    /// Resolve a key press. Returns the text committed by it, which is
+    /// non-empty only when the key closes the current word.
+    fn process_key(&mut self, key: &str) -> String {
+        // Shift keys switch layers, even in Latin fields
+        if let Some(layer) = self.resources.layout.shift_layer(key) {
+            self.press_shift(layer);
+            return String::new();
+        }
+        let layer = self.session.layer;
+        self.session.release_one_shot();
+
+        // URL/email fields and the English layer type the key's own character
+        if layer == Layer::English || self.session.context.latin_passthrough() {
+            return self.handle_special(key);
+        }
+
+        // 1. Table layers: grantha letters, Tamil numerals and signs, digits
+        if let Some(value) = self.resources.layout.layer_lookup(layer, key).cloned() {
+            if layer == Layer::Grantha {
+                self.handle_grantha(&value);
+                return String::new();
+            }
+            return self.handle_special(&value);
+        }
+        // Symbols and numbers are whole layers: a letter key they leave out
+        // types nothing rather than its Tamil letter
+        if matches!(layer, Layer::Symbols | Layer::Numbers) && self.is_letter_key(key) {
+            return String::new();
+        }
+
+        // 2. Check vowels (short, long, or special)
+        let vowel = self.resources.layout.any_vowel_lookup(key, layer == Layer::Nedil).cloned();
 
         if let Some(vowel) = vowel {
-            return self.handle_vowel(&vowel);
+            self.handle_vowel(&vowel);
+            return String::new();
         }
 
-        // 2. Check consonant (base layer)
-        if let Some(consonant) = self.layout.base_lookup(key).cloned() {
-            return self.handle_consonant(&consonant);
+        // 3. Check consonant (base layer)
+        if let Some(consonant) = self.resources.layout.base_lookup(key).cloned() {
+            self.handle_consonant(&consonant);
+            return String::new();
         }
 
-        // 3. Special keys
+        // 4. Special keys
         self.handle_special(key)
     }
 
-    fn handle_vowel(&mut self, vowel: &str) -> String {
-        if let Some(consonant) = self.pending_consonant.take() {
-            if let Some(combined) = self.layout.combine(&consonant, vowel) {
-                self.buffer.push_str(combined);
-                self.current_word.push_str(combined);
-                return combined.clone();
-            } else {
-                self.buffer.push_str(&consonant);
-                self.buffer.push_str(vowel);
-                self.current_word.push_str(&consonant);
-                self.current_word.push_str(vowel);
-                return format!("{}{}", consonant, vowel);
+    /// A key that types a Tamil letter on the base layer
+    fn is_letter_key(&self, key: &str) -> bool {
+        let layout = &self.resources.layout;
+        layout.any_vowel_lookup(key, false).is_some() || layout.base_lookup(key).is_some()
+    }
+
+    fn handle_vowel(&mut self, vowel: &str) {
+        if let Some(consonant) = self.session.pending_consonant.take() {
+            if !self.resources.layout.combine_into(&consonant, vowel, &mut self.session.current_word) {
+                self.session.current_word.push_str(&consonant);
+                self.session.current_word.push_str(vowel);
             }
         } else {
-            self.buffer.push_str(vowel);
-            self.current_word.push_str(vowel);
-            return vowel.to_string();
+            self.session.current_word.push_str(vowel);
         }
     }
 
-    fn handle_consonant(&mut self, consonant: &str) -> String {
-        let mut output = String::new();
-        if let Some(prev) = self.pending_consonant.take() {
-            self.buffer.push_str(&prev);
-            self.current_word.push_str(&prev);
-            output.push_str(&prev);
+    fn handle_consonant(&mut self, consonant: &str) {
+        if let Some(prev) = self.session.pending_consonant.take() {
+            self.session.current_word.push_str(&prev);
         }
-        self.pending_consonant = Some(consonant.to_string());
-        output
+        self.session.pending_consonant = Some(consonant.to_string());
+    }
+
+    /// Grantha consonants wait for a vowel like any mei; complete
+    /// syllables such as ஸ்ரீ go straight into the word.
+    fn handle_grantha(&mut self, letter: &str) {
+        if tamil::ends_with_pulli(letter) {
+            self.handle_consonant(letter);
+            return;
+        }
+        if let Some(pending) = self.session.pending_consonant.take() {
+            self.session.current_word.push_str(&pending);
+        }
+        self.session.current_word.push_str(letter);
     }
 
     fn handle_special(&mut self, key: &str) -> String {
         match key {
-            " " | "space" => {
-                let mut output = String::new();
-                if let Some(pending) = self.pending_consonant.take() {
-                    self.buffer.push_str(&pending);
-                    self.current_word.push_str(&pending);
-                    output.push_str(&pending);
-                }
-                
-                // Word boundary: record word and check sandhi
-                if !self.current_word.is_empty() {
-                    self.dict.record_usage(&self.current_word);
-                    self.words.push(self.current_word.clone());
-                    self.current_word.clear();
-                }
-                
-                output.push(' ');
-                self.buffer.push(' ');
-                output
-            }
-            "backspace" => {
-                if self.pending_consonant.is_some() {
-                    self.pending_consonant = None;
-                } else if !self.current_word.is_empty() {
-                    self.current_word.pop();
-                    self.buffer.pop();
-                } else if !self.buffer.is_empty() {
-                    self.buffer.pop();
-                }
-                "\x08".to_string()
-            }
-            "enter" => {
-                let mut output = String::new();
-                if let Some(pending) = self.pending_consonant.take() {
-                    self.buffer.push_str(&pending);
-                    self.current_word.push_str(&pending);
-                    output.push_str(&pending);
-                }
-                if !self.current_word.is_empty() {
-                    self.words.push(self.current_word.clone());
-                    self.current_word.clear();
-                }
-                output.push('\n');
-                self.buffer.push('\n');
-                output
-            }
-            "nedil" | "swipe_up" => {
-                self.nedil_active = true;
-                String::new()
-            }
-            "clear" => {
-                self.reset();
+            " " | "space" => self.commit_word_with(" "),
+            "enter" => self.commit_word_with("\n"),
+            // A decimal point after digits continues the number: 3.5
+            "." if numerals::is_integer(&self.session.current_word) => {
+                self.session.current_word.push('.');
                 String::new()
             }
-            _ => {
-                let mut output = String::new();
-                if let Some(pending) = self.pending_consonant.take() {
-                    self.buffer.push_str(&pending);
-                    self.current_word.push_str(&pending);
-                    output.push_str(&pending);
+            _ if key.chars().all(is_word_char) => {
+                // Letters and digits extend the composing word
+                if let Some(pending) = self.session.pending_consonant.take() {
+                    self.session.current_word.push_str(&pending);
                 }
-                self.buffer.push_str(key);
-                self.current_word.push_str(key);
-                output.push_str(key);
-                output
+                self.session.current_word.push_str(key);
+                String::new()
             }
+            _ => self.commit_word_with(key),
+        }
+    }
+
+    /// Commit point: move the composing word into the committed buffer,
+    /// followed by `separator`. Returns the committed text.
+    fn commit_word_with(&mut self, separator: &str) -> String {
+        let mut output = self.commit_word();
+        output.push_str(separator);
+        self.session.buffer.push_str(separator);
+        output
+    }
+
+    /// Close the composing word: record it for suggestions and sandhi,
+    /// and move it into the committed buffer. Returns the word.
+    fn commit_word(&mut self) -> String {
+        if let Some(pending) = self.session.pending_consonant.take() {
+            self.session.current_word.push_str(&pending);
+        }
+        // Committing mid-word splits it; the tail stays as plain text
+        self.session.word_tail.clear();
+        let word = std::mem::take(&mut self.session.current_word);
+        if !word.is_empty() {
+            self.learn(&word);
+            self.session.words.push(word.clone());
+            self.session.buffer.push_str(&word);
         }
+        word
+    }
+
+    /// Backspace: edit the composing word if there is one, otherwise
+    /// delete one committed character before the cursor.
+    fn handle_backspace(&mut self) -> EditResult {
+        if self.session.pending_consonant.take().is_some() {
+            return self.edit(0, String::new());
+        }
+        if self.session.current_word.pop().is_some() {
+            return self.edit(0, String::new());
+        }
+        self.session.buffer.pop();
+        // The host may hold text the engine never saw, so always delete
+        self.edit(1, String::new())
+    }
+
+    /// Word backspace: drop the composing word if there is one, otherwise
+    /// the committed word before the cursor with the spacing after it.
+    fn handle_backspace_word(&mut self) -> EditResult {
+        let pending = self.session.pending_consonant.take();
+        if pending.is_some() || !self.session.current_word.is_empty() {
+            self.session.current_word.clear();
+            return self.edit(0, String::new());
+        }
+        let buffer = &self.session.buffer;
+        let word_end = buffer.trim_end_matches(|c| !is_word_char(c)).len();
+        let word_start = buffer[..word_end].trim_end_matches(is_word_char).len();
+        if self.session.words.last().is_some_and(|w| *w == buffer[word_start..word_end]) {
+            self.session.words.pop();
+        }
+        let deleted = buffer[word_start..].chars().count() as u32;
+        self.session.buffer.truncate(word_start);
+        // As with a character backspace, the host may hold more text
+        self.edit(deleted.max(1), String::new())
+    }
+
+    /// Commit the composing word as-is, e.g. when the host loses focus
+    /// or the cursor moves away.
+    pub fn finish_composing(&mut self) -> EditResult {
+        self.session.history.disarm();
+        let commit = self.commit_word();
+        self.session.release_one_shot();
+        self.edit(0, commit)
     }
 
     /// Get word suggestions for the current input prefix.
     /// Returns up to `limit` suggestions ranked by frequency + recency.
     pub fn get_suggestions(&self, limit: u32) -> Vec<String> {
-        if self.current_word.is_empty() {
+        if self.session.current_word.is_empty() || !self.session.context.suggests() {
             return Vec::new();
         }
 
-        let mut prefix = self.current_word.clone();
-        if let Some(ref pending) = self.pending_consonant {
+        // Typed numbers are offered in Tamil numerals; numeric fields
+        // get nothing else
+        let number = &self.session.current_word;
+        if self.session.context.kind == InputKind::Number || numerals::is_number(number) {
+            let mut numbers = numerals::suggestions(number);
+            numbers.truncate(limit as usize);
+            return numbers;
+        }
+
+        let mut prefix = self.session.current_word.clone();
+        if let Some(ref pending) = self.session.pending_consonant {
             prefix.push_str(pending);
         }
 
         self.dict.suggest(&prefix, limit as usize)
     }
 
+    /// Probability per key code of being pressed next, from dictionary
+    /// words continuing the composing word. Keys that lead nowhere get
+    /// 0.0; the map is empty when there is no dictionary signal.
+    pub fn get_key_probabilities(&self) -> HashMap<String, f32> {
+        if !self.session.context.suggests() {
+            return HashMap::new();
+        }
+        hints::next_key_probabilities(
+            &self.resources.layout,
+            &self.dict,
+            self.session.layer,
+            &self.session.current_word,
+            self.session.pending_consonant.as_deref(),
+        )
+    }
+
     /// Get sandhi suggestion for the last two words
     pub fn get_sandhi_suggestion(&self) -> Option<String> {
-        if self.words.is_empty() { return None; }
+        if !self.session.context.offers_sandhi() { return None; }
+        if self.session.words.is_empty() { return None; }
         
-        let last_word = self.words.last()?;
-        if self.current_word.is_empty() { return None; }
+        let last_word = self.session.words.last()?;
+        if self.session.current_word.is_empty() { return None; }
 
-        let result = self.sandhi.analyze(last_word, &self.current_word);
+        let result = self.sandhi.analyze(last_word, &self.session.current_word);
         // Only suggest if a specific rule was applied
         if result.rule != crate::sandhi::SandhiRule::IyalbuPunarchi 
            && result.rule != crate::sandhi::SandhiRule::NoRule 
@@ -204,29 +601,98 @@ impl KeyboardEngine {
         }
     }
 
-    /// Accept a suggestion: replace current word with the suggestion
-    pub fn accept_suggestion(&mut self, suggestion: &str) -> String {
-        // Remove current partial word from buffer
-        let current_len = self.current_word.len();
-        if let Some(ref pending) = self.pending_consonant {
-            // Also account for pending
-            let total = current_len + pending.len();
-            // But pending isn't in buffer yet
+    /// Join the previous word and the composing word using the sandhi
+    /// suggestion. The joined word replaces both and stays composing.
+    pub fn apply_sandhi(&mut self) -> EditResult {
+        self.session.history.disarm();
+        let (joined, last_word) = match (self.get_sandhi_suggestion(), self.session.words.last()) {
+            (Some(joined), Some(last)) => (joined, last.clone()),
+            _ => return self.edit(0, String::new()),
+        };
+
+        // The previous word must be the last committed word before the cursor
+        let word_end = self.session.buffer.trim_end_matches(|c: char| !is_word_char(c)).len();
+        if !self.session.buffer[..word_end].ends_with(&last_word) {
+            return self.edit(0, String::new());
         }
-        
-        // Truncate buffer by current_word length
-        let buf_len = self.buffer.len();
-        if buf_len >= current_len {
-            self.buffer.truncate(buf_len - current_len);
+        let start = word_end - last_word.len();
+
+        let before = self.text_state();
+        let delete_before = self.session.buffer[start..].chars().count() as u32;
+        self.session.buffer.truncate(start);
+        self.session.words.pop();
+        self.session.current_word = joined;
+        self.session.pending_consonant = None;
+        self.session.word_tail.clear();
+        self.record(EditOp::SandhiJoin, before);
+
+        self.edit(delete_before, String::new())
+    }
+
+    /// Accept a suggestion: replace the composing word with it.
+    /// The word stays composing until the next commit point.
+    pub fn accept_suggestion(&mut self, suggestion: &str) -> EditResult {
+        let before = self.text_state();
+        self.session.current_word = suggestion.to_string();
+        self.session.pending_consonant = None;
+        self.learn(suggestion);
+        // The suggestion also replaces the part of the word after the cursor
+        let tail = std::mem::take(&mut self.session.word_tail);
+        self.record(EditOp::AcceptSuggestion, before);
+        let mut edit = self.edit(0, String::new());
+        edit.delete_after = tail.chars().count() as u32;
+        edit
+    }
+
+    /// Re-synchronise with the host text around the cursor, e.g. after the
+    /// user moved it into the middle of a word. Rebuilds `current_word` and
+    /// `words` from the context and returns an edit that turns the word
+    /// part before the cursor into the composing region.
+    ///
+    /// Hosts also call this after applying the engine's own edits. If the
+    /// text is what the engine produced, its state is kept as it is, so
+    /// undo and backspace-reverts-autocorrection survive the resync.
+    pub fn set_surrounding_text(&mut self, before: &str, after: &str) -> EditResult {
+        let tail: String = after.chars().take_while(|&c| is_word_char(c)).collect();
+        if self.is_own_text(before) && tail == self.session.word_tail {
+            let delete_before = self.get_composing().chars().count() as u32;
+            return self.edit(delete_before, String::new());
         }
-        
-        // Replace with suggestion
-        self.buffer.push_str(suggestion);
-        self.current_word = suggestion.to_string();
-        self.pending_consonant = None;
-        self.dict.record_usage(suggestion);
-        
-        suggestion.to_string()
+        // Only text state goes: a locked layer (symbols, English) stays on
+        let (layer, locked) = (self.session.layer, self.session.layer_locked);
+        self.session.clear_text();
+        self.session.history.clear();
+        if locked {
+            self.session.set_layer(layer, true);
+        }
+
+        let word_start = before
+            .char_indices()
+            .rev()
+            .take_while(|&(_, c)| is_word_char(c))
+            .last()
+            .map_or(before.len(), |(idx, _)| idx);
+        let (committed, word) = before.split_at(word_start);
+
+        self.session.buffer.push_str(committed);
+        self.session.words = committed
+            .split(|c: char| !is_word_char(c))
+            .filter(|w| !w.is_empty())
+            .map(String::from)
+            .collect();
+        self.session.current_word.push_str(word);
+        self.session.word_tail = tail;
+        self.session.private_text |= self.incognito;
+
+        let delete_before = self.session.current_word.chars().count() as u32;
+        self.edit(delete_before, String::new())
+    }
+
+    /// True if `before` ends the text the engine has produced, as far as
+    /// the host reports it
+    fn is_own_text(&self, before: &str) -> bool {
+        let own = self.get_buffer();
+        own == before || (own.ends_with(before) && before.chars().count() >= SURROUNDING_CONTEXT)
     }
 
     /// Check if a word is in the dictionary
@@ -236,28 +702,32 @@ impl KeyboardEngine {
 
     /// Translate current word
     pub fn translate_current(&self) -> Option<String> {
-        if self.current_word.is_empty() { return None; }
-        self.dict.translate(&self.current_word)
+        if self.session.current_word.is_empty() { return None; }
+        self.dict.translate(&self.session.current_word)
     }
 
-    /// Get the full current buffer
+    /// Get the full current buffer (committed + composing)
     pub fn get_buffer(&self) -> String {
-        let mut buf = self.buffer.clone();
-        if let Some(ref pending) = self.pending_consonant {
-            buf.push_str(pending);
-        }
+        let mut buf = self.session.buffer.clone();
+        buf.push_str(&self.get_composing());
         buf
     }
 
+    /// Get the composing region: the whole current word including the
+    /// pending consonant, shown underlined by the host
+    pub fn get_composing(&self) -> String {
+        self.get_current_word()
+    }
+
     /// Get pending consonant for UI underline
     pub fn get_pending(&self) -> Option<String> {
-        self.pending_consonant.clone()
+        self.session.pending_consonant.clone()
     }
 
     /// Get current partial word
     pub fn get_current_word(&self) -> String {
-        let mut word = self.current_word.clone();
-        if let Some(ref pending) = self.pending_consonant {
+        let mut word = self.session.current_word.clone();
+        if let Some(ref pending) = self.session.pending_consonant {
             word.push_str(pending);
         }
         word
@@ -265,21 +735,230 @@ impl KeyboardEngine {
 
     /// Check if nedil mode is active
     pub fn is_nedil_active(&self) -> bool {
-        self.nedil_active
+        self.session.layer == Layer::Nedil
+    }
+
+    /// Check if grantha mode is active
+    pub fn is_grantha_active(&self) -> bool {
+        self.session.layer == Layer::Grantha
+    }
+
+    /// Lock or unlock the symbols layer (Tamil numerals and signs)
+    pub fn toggle_symbols(&mut self) {
+        self.toggle_layer(Layer::Symbols);
     }
 
-    /// Get dictionary word count
+    /// Check if the symbols layer is locked on
+    pub fn is_symbols_active(&self) -> bool {
+        self.session.layer == Layer::Symbols
+    }
+
+    /// Get dictionary word count (0 while it is loading)
     pub fn dictionary_size(&self) -> u32 {
         self.dict.word_count()
     }
 
-    /// Reset engine state
+    /// False while the dictionary loads in the background: typing works,
+    /// but there are no suggestions, key hints or glides yet
+    pub fn is_dictionary_ready(&self) -> bool {
+        self.dict.is_ready()
+    }
+
+    pub fn resources(&self) -> &Arc<Resources> {
+        &self.resources
+    }
+
+    /// Revert the last recorded operation
+    pub fn undo(&mut self) -> EditResult {
+        match self.session.history.undo(self.text_state()) {
+            Some(state) => self.restore_text_state(state),
+            None => self.edit(0, String::new()),
+        }
+    }
+
+    /// Re-apply the last undone operation
+    pub fn redo(&mut self) -> EditResult {
+        match self.session.history.redo(self.text_state()) {
+            Some(state) => self.restore_text_state(state),
+            None => self.edit(0, String::new()),
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.session.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.session.history.can_redo()
+    }
+
+    fn text_state(&self) -> TextState {
+        self.session.text_state()
+    }
+
+    /// Record `op` if it changed the text state
+    fn record(&mut self, op: EditOp, before: TextState) {
+        if !self.session.differs_from(&before) {
+            return;
+        }
+        if self.incognito {
+            self.session.private_text = true;
+        } else if self.session.context.keeps_history() {
+            self.session.history.record(op, before);
+        }
+    }
+
+    /// Switch to `state` and return the edit that takes the host text
+    /// there. Text deleted after the cursor is not restored.
+    fn restore_text_state(&mut self, state: TextState) -> EditResult {
+        let old_buffer = std::mem::replace(&mut self.session.buffer, state.buffer);
+        self.session.words = state.words;
+        self.session.current_word = state.current_word;
+        self.session.pending_consonant = state.pending_consonant;
+        self.session.word_tail.clear();
+        self.session.release_one_shot();
+
+        // Both buffers end at the composing region; keep their common prefix
+        let common = old_buffer
+            .char_indices()
+            .zip(self.session.buffer.chars())
+            .find(|&((_, a), b)| a != b)
+            .map_or(old_buffer.len().min(self.session.buffer.len()), |((idx, _), _)| idx);
+        let delete_before = old_buffer[common..].chars().count() as u32;
+        let commit = self.session.buffer[common..].to_string();
+        self.edit(delete_before, commit)
+    }
+
+    /// Serialize the engine state to a versioned blob the host can persist
+    pub fn snapshot(&self) -> String {
+        let mut snapshot = EngineSnapshot {
+            version: SNAPSHOT_VERSION,
+            active_session: self.active_session.clone(),
+            recents: self.dict.recents(),
+            ..Default::default()
+        };
+        // Incognito and password text never leaves memory
+        if !self.incognito {
+            let active = std::iter::once((&self.active_session, &self.session));
+            for (name, session) in active.chain(&self.sessions) {
+                if session.context.keeps_history() && !session.private_text {
+                    snapshot.sessions.insert(name.clone(), SessionSnapshot::of(session));
+                }
+            }
+        }
+        snapshot.to_json()
+    }
+
+    /// Rebuild the engine state from a `snapshot()` blob, replacing every
+    /// session. Fails, leaving the engine untouched, if the blob is invalid.
+    pub fn restore(&mut self, blob: &str) -> Result<(), IllakiyaError> {
+        let mut snapshot = EngineSnapshot::from_json(blob)?;
+        self.session = snapshot
+            .sessions
+            .remove(&snapshot.active_session)
+            .map_or_else(Session::default, SessionSnapshot::into_session);
+        self.active_session = snapshot.active_session;
+        self.sessions = snapshot
+            .sessions
+            .into_iter()
+            .map(|(name, session)| (name, session.into_session()))
+            .collect();
+        self.dict.set_recents(snapshot.recents);
+        Ok(())
+    }
+
+    /// Record a typed word for recency boosting, if the field allows it.
+    /// All learning from typed text must go through here.
+    fn learn(&mut self, word: &str) {
+        if !self.incognito && self.session.context.learns() {
+            self.dict.record_usage(word);
+        }
+    }
+
+    /// Incognito mode: disables dictionary and sandhi learning and undo
+    /// history, for every session. Sessions typed in while it is on stay
+    /// out of snapshots after it is turned off, until they are reset.
+    pub fn set_incognito(&mut self, enabled: bool) {
+        self.session.history.disarm();
+        self.incognito = enabled;
+        self.dict.set_learning_enabled(!enabled);
+        self.sandhi.set_learning_enabled(!enabled);
+    }
+
+    pub fn is_incognito(&self) -> bool {
+        self.incognito
+    }
+
+    /// Describe the focused input field; applies to the active session.
+    /// A field that turns into or out of a password field (a "show
+    /// password" toggle) drops its text and history; text from a password
+    /// field also keeps the session out of snapshots until it is reset.
+    pub fn set_input_context(&mut self, context: InputContext) {
+        let was_private = !self.session.context.keeps_history();
+        if self.session.context.keeps_history() != context.keeps_history() {
+            self.session.clear_text();
+            self.session.history.clear();
+            self.session.private_text |= was_private;
+        }
+        self.session.history.disarm();
+        self.session.context = context;
+    }
+
+    pub fn get_input_context(&self) -> InputContext {
+        self.session.context
+    }
+
+    /// Reset the active session
     pub fn reset(&mut self) {
-        self.buffer.clear();
-        self.pending_consonant = None;
-        self.nedil_active = false;
-        self.words.clear();
-        self.current_word.clear();
+        self.session.reset();
+    }
+
+    /// Create an empty session for an input field. No-op if it exists.
+    pub fn create_session(&mut self, name: &str) {
+        if name != self.active_session && !self.sessions.contains_key(name) {
+            self.sessions.insert(name.to_string(), Session::default());
+        }
+    }
+
+    /// Make `name` the active session, creating it if needed.
+    /// The host should finish composing in the old field first.
+    pub fn switch_session(&mut self, name: &str) {
+        if name == self.active_session {
+            return;
+        }
+        let next = self.sessions.remove(name).unwrap_or_default();
+        let mut prev = std::mem::replace(&mut self.session, next);
+        prev.history.disarm();
+        let prev_name = std::mem::replace(&mut self.active_session, name.to_string());
+        self.sessions.insert(prev_name, prev);
+    }
+
+    /// Drop a session. Destroying the active session switches back to a
+    /// fresh default session.
+    pub fn destroy_session(&mut self, name: &str) {
+        if name != self.active_session {
+            self.sessions.remove(name);
+            return;
+        }
+        self.session = self.sessions.remove(DEFAULT_SESSION).unwrap_or_default();
+        self.active_session = DEFAULT_SESSION.to_string();
+        if name == DEFAULT_SESSION {
+            self.session.reset();
+        }
+    }
+
+    /// Name of the active session
+    pub fn active_session(&self) -> String {
+        self.active_session.clone()
+    }
+
+    /// Names of all sessions, active one first
+    pub fn session_names(&self) -> Vec<String> {
+        let mut names = vec![self.active_session.clone()];
+        let mut parked: Vec<String> = self.sessions.keys().cloned().collect();
+        parked.sort();
+        names.extend(parked);
+        names
     }
 }
 
@@ -289,28 +968,28 @@ mod tests {
 
     #[test]
     fn test_vowel_standalone() {
-        let mut eng = KeyboardEngine::new();
-        assert_eq!(eng.process_input("z"), "அ");
+        let mut eng = Engine::new().unwrap();
+        assert_eq!(eng.process_input("z").composing, "அ");
     }
 
     #[test]
     fn test_consonant_then_vowel() {
-        let mut eng = KeyboardEngine::new();
-        assert_eq!(eng.process_input("q"), "");
-        assert_eq!(eng.process_input("z"), "க");
+        let mut eng = Engine::new().unwrap();
+        assert_eq!(eng.process_input("q").composing, "க்");
+        assert_eq!(eng.process_input("z").composing, "க");
     }
 
     #[test]
     fn test_long_vowel_nedil() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         eng.process_input("q");
         eng.process_input("nedil");
-        assert_eq!(eng.process_input("z"), "கா");
+        assert_eq!(eng.process_input("z").composing, "கா");
     }
 
     #[test]
     fn test_suggestions() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         // Type "தமி" -> should suggest "தமிழ்"
         eng.process_input("u"); // த் pending
         eng.process_input("z"); // த
@@ -323,24 +1002,24 @@ mod tests {
 
     #[test]
     fn test_word_boundary_tracking() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         eng.process_input("z"); // அ
         eng.process_input(" "); // Space -> word boundary
-        assert_eq!(eng.words.len(), 1);
-        assert_eq!(eng.words[0], "அ");
-        assert!(eng.current_word.is_empty());
+        assert_eq!(eng.session.words.len(), 1);
+        assert_eq!(eng.session.words[0], "அ");
+        assert!(eng.session.current_word.is_empty());
     }
 
     #[test]
     fn test_dictionary_lookup() {
-        let eng = KeyboardEngine::new();
+        let eng = Engine::new().unwrap();
         assert!(eng.is_valid_word("நான்"));
         assert!(!eng.is_valid_word("abcdef"));
     }
 
     #[test]
     fn test_current_word_tracking() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         eng.process_input("u"); // த் pending
         eng.process_input("z"); // த committed
         assert_eq!(eng.get_current_word(), "த");
@@ -350,23 +1029,873 @@ mod tests {
 
     #[test]
     fn test_dictionary_size() {
-        let eng = KeyboardEngine::new();
+        let eng = Engine::new().unwrap();
         assert!(eng.dictionary_size() >= 100);
     }
 
     #[test]
     fn test_accept_suggestion() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         eng.process_input("u"); // த் pending
         eng.process_input("z"); // த
         let result = eng.accept_suggestion("தமிழ்");
-        assert_eq!(result, "தமிழ்");
+        assert_eq!(result.commit, "");
+        assert_eq!(result.composing, "தமிழ்");
         assert_eq!(eng.get_current_word(), "தமிழ்");
     }
 
+    #[test]
+    fn test_pending_consonant_is_composing() {
+        let mut eng = Engine::new().unwrap();
+        let edit = eng.process_input("q");
+        assert_eq!(edit.commit, "");
+        assert_eq!(edit.composing, "க்");
+        assert!(edit.pending);
+        let edit = eng.process_input("z");
+        assert_eq!(edit.commit, "");
+        assert_eq!(edit.composing, "க");
+        assert!(!edit.pending);
+    }
+
+    #[test]
+    fn test_whole_word_is_composing() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        let edit = eng.process_input("p"); // ம் pending
+        assert_eq!(edit.composing, "தம்");
+        assert_eq!(edit.commit, "");
+    }
+
+    #[test]
+    fn test_space_commits_word() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.process_input("p"); // ம் pending
+        let edit = eng.process_input("space");
+        assert_eq!(edit.commit, "தம் ");
+        assert_eq!(edit.composing, "");
+        assert_eq!(eng.session.words, vec!["தம்".to_string()]);
+        assert_eq!(eng.get_buffer(), "தம் ");
+    }
+
+    #[test]
+    fn test_punctuation_is_commit_point() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        let edit = eng.process_input(",");
+        assert_eq!(edit.commit, "அ,");
+        assert_eq!(edit.composing, "");
+    }
+
+    #[test]
+    fn test_finish_composing() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("q"); // க் pending
+        let edit = eng.finish_composing();
+        assert_eq!(edit.commit, "அக்");
+        assert_eq!(edit.composing, "");
+        assert_eq!(eng.get_buffer(), "அக்");
+    }
+
+    #[test]
+    fn test_backspace_drops_pending_without_deleting() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("q"); // க் pending
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.delete_before, 0);
+        assert_eq!(edit.composing, "அ");
+        assert_eq!(eng.get_buffer(), "அ");
+    }
+
+    #[test]
+    fn test_backspace_edits_composing_word() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("x"); // தி
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.delete_before, 0);
+        assert_eq!(edit.composing, "த");
+    }
+
+    #[test]
+    fn test_backspace_deletes_committed_char() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.delete_before, 1);
+        assert_eq!(edit.commit, "");
+        assert_eq!(eng.get_buffer(), "அ");
+    }
+
+    #[test]
+    fn test_nedil_flag_reported() {
+        let mut eng = Engine::new().unwrap();
+        assert!(eng.process_input("nedil").nedil_active);
+        assert!(!eng.process_input("z").nedil_active);
+    }
+
+    #[test]
+    fn test_surrounding_text_rebuilds_context() {
+        let mut eng = Engine::new().unwrap();
+        let edit = eng.set_surrounding_text("நான் தம", "ிழ் பேசு");
+        assert_eq!(edit.delete_before, 2);
+        assert_eq!(edit.composing, "தம");
+        assert_eq!(eng.session.words, vec!["நான்".to_string()]);
+        assert_eq!(eng.get_current_word(), "தம");
+        assert_eq!(eng.get_buffer(), "நான் தம");
+    }
+
+    #[test]
+    fn test_surrounding_text_at_word_boundary() {
+        let mut eng = Engine::new().unwrap();
+        let edit = eng.set_surrounding_text("நான் ", "");
+        assert_eq!(edit.delete_before, 0);
+        assert_eq!(edit.composing, "");
+        assert!(eng.get_current_word().is_empty());
+    }
+
+    #[test]
+    fn test_table_layers_type_their_labels() {
+        let base = Engine::new().unwrap();
+        let layout = &base.resources.layout;
+        let keys: Vec<String> = layout.geometry.rows.iter().flatten()
+            .map(|k| k.key.clone())
+            .filter(|k| layout.shift_layer(k).is_none() && !["space", "enter", "backspace"].contains(&k.as_str()))
+            .collect();
+        for layer in [Layer::Symbols, Layer::Numbers] {
+            let labels = layout.labels(layer);
+            for key in &keys {
+                let mut eng = Engine::new().unwrap();
+                eng.set_layer(layer, true);
+                let edit = eng.process_input(key);
+                let typed = format!("{}{}", edit.commit, edit.composing);
+                assert!(typed.is_empty() || labels.get(key) == Some(&typed),
+                    "{:?} {} typed {:?}", layer, key, typed);
+            }
+        }
+    }
+
+    #[test]
+    fn test_surrounding_text_keeps_locked_layer() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_layer(Layer::English, true);
+        eng.set_surrounding_text("நான் ", "");
+        assert_eq!(eng.get_layer(), Layer::English);
+        // A one-shot shift is dropped with the text
+        eng.set_layer(Layer::Nedil, false);
+        eng.set_surrounding_text("அ ", "");
+        assert_eq!(eng.get_layer(), Layer::Base);
+    }
+
+    #[test]
+    fn test_accept_suggestion_mid_word_replaces_tail() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_surrounding_text("நான் தம", "ிழ் பேசு");
+        assert!(eng.get_suggestions(5).iter().any(|s| s == "தமிழ்"));
+        let edit = eng.accept_suggestion("தமிழ்");
+        assert_eq!(edit.delete_after, 3); // ி ழ ்
+        assert_eq!(edit.composing, "தமிழ்");
+    }
+
+    #[test]
+    fn test_typing_after_reentry_extends_word() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_surrounding_text("த", "");
+        eng.process_input("p"); // ம் pending
+        let edit = eng.process_input("x"); // மி
+        assert_eq!(edit.composing, "தமி");
+        assert_eq!(edit.delete_before, 0);
+    }
+
+    #[test]
+    fn test_undo_reverts_typing_run() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        let edit = eng.undo();
+        assert_eq!(edit.delete_before, 0);
+        assert_eq!(edit.composing, "");
+        assert_eq!(eng.get_buffer(), "அ ");
+        let edit = eng.undo();
+        assert_eq!(edit.delete_before, 2);
+        assert_eq!(edit.composing, "");
+        assert_eq!(eng.get_buffer(), "");
+        assert!(!eng.can_undo());
+    }
+
+    #[test]
+    fn test_redo_after_undo() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+        eng.undo();
+        let edit = eng.redo();
+        assert_eq!(edit.commit, "அ ");
+        assert_eq!(eng.get_buffer(), "அ ");
+        assert!(!eng.can_redo());
+    }
+
+    #[test]
+    fn test_backspace_reverts_accepted_suggestion() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.accept_suggestion("தமிழ்");
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.composing, "த");
+        assert_eq!(eng.get_current_word(), "த");
+        // A second backspace deletes normally
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.composing, "");
+    }
+
+    #[test]
+    fn test_resync_with_own_text_keeps_history() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.accept_suggestion("தமிழ்");
+        // The host committed the composing region and reports it back
+        let edit = eng.set_surrounding_text("தமிழ்", "");
+        assert_eq!((edit.delete_before, edit.composing.as_str()), (5, "தமிழ்"));
+        assert!(eng.can_undo());
+        // Backspace still reverts the autocorrection
+        assert_eq!(eng.process_input("backspace").composing, "த");
+    }
+
+    #[test]
+    fn test_layer_switch_disarms_autocorrect() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.accept_suggestion("தமிழ்");
+        // The nedil toggle changes no text but still follows the accept
+        eng.toggle_nedil();
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.composing, "தமிழ");
+        assert!(!eng.session.history.autocorrect_armed());
+    }
+
+    #[test]
+    fn test_word_backspace() {
+        let mut eng = Engine::new().unwrap();
+        for key in ["i", "z", "k", "space", "u", "z", "p"] {
+            eng.process_input(key);
+        }
+        // த + ம் pending: the composing word goes first
+        let edit = eng.process_event(InputEvent::Backspace { mode: BackspaceMode::Word });
+        assert_eq!((edit.delete_before, edit.composing.as_str()), (0, ""));
+        // Then "நன் "
+        let edit = eng.process_event(InputEvent::Backspace { mode: BackspaceMode::Word });
+        assert_eq!(edit.delete_before, 4);
+        assert_eq!(eng.get_buffer(), "");
+        assert_eq!(eng.undo().composing, "");
+        assert_eq!(eng.get_buffer(), "நன் ");
+    }
+
+    #[test]
+    fn test_literal_clear_is_text() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z");
+        let edit = eng.process_event(InputEvent::KeyTap { key: "clear".into() });
+        assert_eq!(edit.composing, "அclear");
+        // The legacy key code still clears
+        assert_eq!(eng.process_input("clear").composing, "");
+    }
+
+    #[test]
+    fn test_commit_text_and_layer_events() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z");
+        let edit = eng.process_event(InputEvent::CommitText { text: "🙂".into() });
+        assert_eq!(edit.commit, "அ🙂");
+        let edit = eng.process_event(InputEvent::SetLayer { layer: Layer::Symbols, locked: true });
+        assert_eq!(edit.layer, Layer::Symbols);
+        assert_eq!(eng.process_event(InputEvent::Enter).commit, "\n");
+    }
+
+    #[test]
+    fn test_apply_sandhi_joins_words() {
+        let mut eng = Engine::new().unwrap();
+        // பூ + கொடி
+        eng.process_input("o");
+        eng.process_input("nedil");
+        eng.process_input("c");
+        eng.process_input("space");
+        eng.process_input("q");
+        eng.process_input("b");
+        eng.process_input("t");
+        eng.process_input("x");
+        assert_eq!(eng.get_sandhi_suggestion().as_deref(), Some("பூக்கொடி"));
+        let edit = eng.apply_sandhi();
+        assert_eq!(edit.delete_before, 3); // ப ூ + space
+        assert_eq!(edit.composing, "பூக்கொடி");
+        assert_eq!(eng.get_buffer(), "பூக்கொடி");
+        assert!(eng.session.words.is_empty());
+
+        // Backspace right after the join restores both words
+        let edit = eng.process_input("backspace");
+        assert_eq!(edit.commit, "பூ ");
+        assert_eq!(edit.composing, "கொடி");
+        assert_eq!(eng.session.words, vec!["பூ".to_string()]);
+    }
+
+    #[test]
+    fn test_clear_can_be_undone() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.process_input("clear");
+        assert_eq!(eng.get_buffer(), "");
+        let edit = eng.undo();
+        assert_eq!(edit.composing, "த");
+    }
+
+    #[test]
+    fn test_snapshot_restore_roundtrip() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.process_input("p"); // ம் pending
+        eng.process_input("nedil");
+        let blob = eng.snapshot();
+
+        let mut restored = Engine::new().unwrap();
+        assert!(restored.restore(&blob).is_ok());
+        assert_eq!(restored.get_buffer(), "அ தம்");
+        assert_eq!(restored.get_pending(), Some("ம்".to_string()));
+        assert_eq!(restored.session.words, vec!["அ".to_string()]);
+        assert!(restored.is_nedil_active());
+        assert_eq!(restored.snapshot(), blob);
+
+        // Typing continues where it left off
+        assert_eq!(restored.process_input("z").composing, "தமா");
+    }
+
+    #[test]
+    fn test_snapshot_keeps_every_session() {
+        let mut eng = Engine::new().unwrap();
+        eng.switch_session("chat");
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+        eng.switch_session("login");
+        set_kind(&mut eng, InputKind::Password);
+        eng.process_input("u"); // த் pending
+        eng.switch_session("search");
+        eng.set_layer(Layer::Symbols, true);
+        let blob = eng.snapshot();
+        assert!(!blob.contains("த்"));
+
+        let mut restored = Engine::new().unwrap();
+        assert!(restored.restore(&blob).is_ok());
+        assert_eq!(restored.active_session(), "search");
+        assert_eq!(restored.get_layer(), Layer::Symbols);
+        assert!(restored.is_layer_locked());
+        assert_eq!(restored.session_names(), vec!["search", "chat", "default"]);
+
+        restored.switch_session("chat");
+        assert_eq!(restored.get_buffer(), "அ ");
+        assert_eq!(restored.session.words, vec!["அ".to_string()]);
+        assert_eq!(restored.get_layer(), Layer::Base);
+        restored.switch_session("login");
+        assert_eq!(restored.get_buffer(), "");
+    }
+
+    #[test]
+    fn test_restore_rejects_invalid_blob() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        assert!(matches!(eng.restore("{\"version\": 99}"), Err(IllakiyaError::UserDataIo { .. })));
+        assert_eq!(eng.get_buffer(), "அ");
+    }
+
+    #[test]
+    fn test_custom_layout() {
+        assert!(matches!(Engine::with_layout("{}"), Err(IllakiyaError::LayoutInvalid { .. })));
+        let json = include_str!("../../data/layouts/pm0100.json").replace(r#""q": "க்""#, r#""q": "ச்""#);
+        let mut eng = Engine::with_layout(&json).unwrap();
+        assert_eq!(eng.process_input("q").composing, "ச்");
+    }
+
+    #[test]
+    fn test_sessions_keep_separate_context() {
+        let mut eng = Engine::new().unwrap();
+        eng.create_session("chat");
+        eng.switch_session("chat");
+        eng.process_input("z"); // அ
+        eng.process_input("space");
+
+        eng.switch_session("search");
+        assert_eq!(eng.get_buffer(), "");
+        eng.process_input("u"); // த் pending
+
+        eng.switch_session("chat");
+        assert_eq!(eng.get_buffer(), "அ ");
+        assert_eq!(eng.session.words, vec!["அ".to_string()]);
+        assert_eq!(eng.get_pending(), None);
+
+        eng.switch_session("search");
+        assert_eq!(eng.get_pending(), Some("த்".to_string()));
+    }
+
+    #[test]
+    fn test_destroy_active_session_falls_back_to_default() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ in default
+        eng.switch_session("chat");
+        eng.process_input("x"); // இ in chat
+        eng.destroy_session("chat");
+        assert_eq!(eng.active_session(), DEFAULT_SESSION);
+        assert_eq!(eng.get_buffer(), "அ");
+        assert_eq!(eng.session_names(), vec![DEFAULT_SESSION.to_string()]);
+    }
+
+    #[test]
+    fn test_sessions_share_dictionary_recents() {
+        let mut eng = Engine::new().unwrap();
+        eng.switch_session("chat");
+        eng.accept_suggestion("வணக்கம்");
+        eng.switch_session("search");
+        assert_eq!(eng.dict.recents().first().map(String::as_str), Some("வணக்கம்"));
+    }
+
+    fn set_kind(eng: &mut Engine, kind: InputKind) {
+        eng.set_input_context(InputContext { kind, no_learning: false });
+    }
+
+    #[test]
+    fn test_password_field_learns_and_suggests_nothing() {
+        let mut eng = Engine::new().unwrap();
+        set_kind(&mut eng, InputKind::Password);
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        assert!(eng.get_suggestions(5).is_empty());
+        eng.process_input("space");
+        assert!(eng.dict.recents().is_empty());
+        assert!(!eng.can_undo());
+    }
+
+    #[test]
+    fn test_no_learning_flag_keeps_suggestions() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_input_context(InputContext { kind: InputKind::Text, no_learning: true });
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        assert!(!eng.get_suggestions(5).is_empty());
+        eng.accept_suggestion("தமிழ்");
+        assert!(eng.dict.recents().is_empty());
+    }
+
+    #[test]
+    fn test_url_field_types_latin() {
+        let mut eng = Engine::new().unwrap();
+        set_kind(&mut eng, InputKind::Url);
+        eng.process_input("q");
+        eng.process_input("z");
+        assert_eq!(eng.get_composing(), "qz");
+        assert!(eng.get_suggestions(5).is_empty());
+    }
+
+    #[test]
+    fn test_number_field_offers_tamil_numerals() {
+        let mut eng = Engine::new().unwrap();
+        set_kind(&mut eng, InputKind::Number);
+        eng.process_input("2");
+        eng.process_input("0");
+        eng.process_input("2");
+        eng.process_input("4");
+        assert_eq!(eng.get_suggestions(5), vec!["௨௦௨௪", "௨௲௨௰௪"]);
+        assert_eq!(eng.get_suggestions(1), vec!["௨௦௨௪"]);
+    }
+
+    #[test]
+    fn test_digits_in_text_field_offer_numerals() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("1");
+        eng.process_input("0");
+        assert_eq!(eng.get_suggestions(5), vec!["௧௦", "௰"]);
+    }
+
+    #[test]
+    fn test_decimal_point_continues_number() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("3");
+        eng.process_input(".");
+        assert_eq!(eng.process_input("5").composing, "3.5");
+        assert_eq!(eng.get_suggestions(5), vec!["௩.௫"]);
+        // After a word or a second point, "." is punctuation
+        assert_eq!(eng.process_input(".").commit, "3.5.");
+        eng.process_input("z"); // அ
+        assert_eq!(eng.process_input(".").commit, "அ.");
+    }
+
+    #[test]
+    fn test_symbols_layer_types_numerals() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("symbols");
+        assert!(eng.is_symbols_active());
+        eng.process_input("w"); // ௨
+        eng.process_input("d"); // ௲
+        assert_eq!(eng.get_composing(), "௨௲");
+        // Locked: stays on until toggled off
+        eng.process_input("symbols");
+        assert_eq!(eng.process_input("q").composing, "௨௲க்");
+    }
+
+    #[test]
+    fn test_search_field_has_no_sandhi() {
+        let mut eng = Engine::new().unwrap();
+        set_kind(&mut eng, InputKind::Search);
+        // பூ + க
+        eng.process_input("o");
+        eng.process_input("nedil");
+        eng.process_input("c");
+        eng.process_input("space");
+        eng.process_input("q");
+        eng.process_input("z");
+        assert_eq!(eng.get_sandhi_suggestion(), None);
+    }
+
+    #[test]
+    fn test_input_context_is_per_session() {
+        let mut eng = Engine::new().unwrap();
+        eng.switch_session("login");
+        set_kind(&mut eng, InputKind::Password);
+        eng.switch_session("chat");
+        assert_eq!(eng.get_input_context().kind, InputKind::Text);
+    }
+
+    /// Type "வணக்கம் தமிழ" and accept a suggestion
+    fn type_private_text(eng: &mut Engine) {
+        eng.accept_suggestion("வணக்கம்");
+        eng.process_input("space");
+        eng.process_input("u"); // த் pending
+        eng.process_input("z"); // த
+        eng.process_input("p"); // ம் pending
+        eng.process_input("x"); // மி
+        eng.process_input("g"); // ழ் pending
+    }
+
+    #[test]
+    fn test_incognito_retains_nothing_typed() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_incognito(true);
+        type_private_text(&mut eng);
+        eng.sandhi.record_correction("பூ", "கொடி", "பூக்கொடி");
+
+        let blob = eng.snapshot();
+        for typed in ["வணக்கம்", "தமி", "ழ்"] {
+            assert!(!blob.contains(typed), "snapshot leaked {:?}: {}", typed, blob);
+        }
+        assert!(eng.dict.recents().is_empty());
+        assert_eq!(eng.sandhi.get_corrections_count(), 0);
+
+        // Typing itself still works
+        assert_eq!(eng.get_buffer(), "வணக்கம் தமிழ்");
+    }
+
+    #[test]
+    fn test_incognito_applies_to_all_sessions() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_incognito(true);
+        eng.switch_session("chat");
+        type_private_text(&mut eng);
+        assert!(!eng.snapshot().contains("வணக்கம்"));
+        assert!(eng.dict.recents().is_empty());
+    }
+
+    #[test]
+    fn test_private_text_stays_out_after_leaving() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_incognito(true);
+        type_private_text(&mut eng);
+        assert!(!eng.can_undo());
+        eng.set_incognito(false);
+        assert!(!eng.is_incognito());
+        let blob = eng.snapshot();
+        for typed in ["வணக்கம்", "தமி", "ழ்"] {
+            assert!(!blob.contains(typed), "snapshot leaked {:?}: {}", typed, blob);
+        }
+
+        // Other fields learn and persist again
+        eng.switch_session("chat");
+        type_private_text(&mut eng);
+        assert!(eng.snapshot().contains("வணக்கம்"));
+        assert_eq!(eng.dict.recents().first().map(String::as_str), Some("வணக்கம்"));
+    }
+
+    #[test]
+    fn test_password_shown_as_text_drops_it() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        set_kind(&mut eng, InputKind::Password);
+        assert_eq!(eng.get_buffer(), "");
+        assert!(!eng.can_undo());
+        type_private_text(&mut eng);
+
+        // "Show password" restarts the same field as plain text
+        set_kind(&mut eng, InputKind::Text);
+        assert_eq!(eng.get_buffer(), "");
+        assert!(!eng.can_undo());
+        eng.set_surrounding_text("வணக்கம் தமி", "");
+        assert_eq!(eng.get_composing(), "தமி");
+        assert!(!eng.snapshot().contains("தமி"));
+
+        // Until the field is reset
+        eng.reset();
+        eng.process_input("z"); // அ
+        assert!(eng.snapshot().contains("அ"));
+    }
+
+    #[test]
+    fn test_password_text_not_in_snapshot() {
+        let mut eng = Engine::new().unwrap();
+        set_kind(&mut eng, InputKind::Password);
+        type_private_text(&mut eng);
+        assert!(!eng.snapshot().contains("தமி"));
+    }
+
+    #[test]
+    fn test_grantha_consonant_with_vowel() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("grantha");
+        assert!(eng.is_grantha_active());
+        assert_eq!(eng.process_input("j").composing, "ஜ்");
+        assert!(!eng.is_grantha_active());
+        eng.process_input("nedil");
+        assert_eq!(eng.process_input("z").composing, "ஜா");
+    }
+
+    #[test]
+    fn test_grantha_is_one_shot() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("long_press");
+        eng.process_input("h"); // ஹ் pending
+        eng.process_input("z"); // ஹ
+        eng.process_input("h"); // base layer again: ள் pending
+        assert_eq!(eng.get_composing(), "ஹள்");
+    }
+
+    #[test]
+    fn test_grantha_sri() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("grantha");
+        let edit = eng.process_input("l");
+        assert_eq!(edit.composing, "ஸ்ரீ");
+        assert!(!edit.pending);
+    }
+
+    #[test]
+    fn test_grantha_on_non_grantha_key_falls_back() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("grantha");
+        assert_eq!(eng.process_input("z").composing, "அ");
+        assert!(!eng.is_grantha_active());
+    }
+
+    #[test]
+    fn test_nedil_pressed_twice_locks() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("nedil");
+        eng.process_input("nedil");
+        assert!(eng.is_layer_locked());
+        eng.process_input("z"); // ஆ
+        eng.process_input("x"); // ஈ
+        assert_eq!(eng.get_composing(), "ஆஈ");
+        eng.process_input("nedil");
+        assert_eq!(eng.get_layer(), Layer::Base);
+    }
+
+    #[test]
+    fn test_key_labels_follow_layer() {
+        let mut eng = Engine::new().unwrap();
+        assert_eq!(eng.get_key_labels()["z"], "அ");
+        let edit = eng.process_input("nedil");
+        assert_eq!(edit.layer, Layer::Nedil);
+        assert_eq!(eng.get_key_labels()["z"], "ஆ");
+        eng.process_input("z");
+        assert_eq!(eng.get_key_labels()["z"], "அ");
+        set_kind(&mut eng, InputKind::Url);
+        assert_eq!(eng.get_key_labels()["z"], "z");
+    }
+
+    #[test]
+    fn test_keyboard_model_tracks_pending() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("o"); // ப்
+        let model = eng.get_keyboard_model();
+        let key = |k: &str| model.rows.iter().flatten().find(|m| m.key == k).unwrap().clone();
+        assert!(key("o").valid); // ப்ப
+        assert!(!key("q").valid); // ப்க
+        eng.process_input("z");
+        assert!(eng.get_keyboard_model().rows.iter().flatten().all(|k| k.valid));
+    }
+
+    #[test]
+    fn test_key_probabilities_follow_composing_word() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த்
+        eng.process_input("z"); // த
+        eng.process_input("p"); // ம் pending
+        let p = eng.get_key_probabilities();
+        assert!(p["x"] > 0.99); // தமி
+        set_kind(&mut eng, InputKind::Password);
+        assert!(eng.get_key_probabilities().is_empty());
+    }
+
+    /// Tap halfway between the இ (x) and உ (c) keys on the vowel row
+    const BETWEEN_X_AND_C: (f32, f32) = (0.35, 0.625);
+
+    #[test]
+    fn test_tap_uses_dictionary_to_pick_key() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u"); // த்
+        eng.process_input("z"); // த
+        eng.process_input("p"); // ம் pending; only தமி continues
+        let (x, y) = BETWEEN_X_AND_C;
+        assert_eq!(eng.process_tap(x, y).composing, "தமி");
+        let alternatives = eng.get_tap_alternatives();
+        assert_eq!(alternatives[0].key, "x");
+        assert!(alternatives.iter().any(|c| c.key == "c"));
+    }
+
+    #[test]
+    fn test_correct_last_tap() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("u");
+        eng.process_input("z");
+        eng.process_input("p");
+        let (x, y) = BETWEEN_X_AND_C;
+        eng.process_tap(x, y);
+        let edit = eng.correct_last_tap("c");
+        assert_eq!(edit.composing, "தமு");
+        assert_eq!(edit.delete_before, 0);
+        assert!(eng.get_tap_alternatives().is_empty());
+    }
+
+    #[test]
+    fn test_tap_alternatives_go_stale() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_tap(0.05, 0.125); // க்
+        assert!(!eng.get_tap_alternatives().is_empty());
+        eng.process_input("z");
+        assert!(eng.get_tap_alternatives().is_empty());
+        assert_eq!(eng.correct_last_tap("w").composing, "க");
+    }
+
+    #[test]
+    fn test_flick_types_uyirmei() {
+        let mut eng = Engine::new().unwrap();
+        assert_eq!(eng.process_flick("u", FlickDirection::Up).composing, "த");
+        eng.process_input("p");
+        assert_eq!(eng.process_flick("a", FlickDirection::Up).composing, "தம்ய");
+        eng.process_input("clear");
+
+        eng.process_input("swipe_up");
+        let edit = eng.process_flick("q", FlickDirection::Right);
+        assert_eq!(edit.composing, "கூ");
+        assert_eq!(edit.layer, Layer::Base);
+    }
+
+    #[test]
+    fn test_flick_grantha_and_non_consonant_keys() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("long_press");
+        assert_eq!(eng.process_flick("j", FlickDirection::UpRight).composing, "ஜி");
+        // ஃ and vowel keys take no flick: typed as a tap
+        assert_eq!(eng.process_flick("l", FlickDirection::Up).composing, "ஜிஃ");
+        assert_eq!(eng.process_flick("z", FlickDirection::Up).composing, "ஜிஃஅ");
+    }
+
+    #[test]
+    fn test_physical_keys() {
+        let mut eng = Engine::new().unwrap();
+        // U then Z: த
+        eng.process_key_code(22, false);
+        assert_eq!(eng.process_key_code(44, false).unwrap().composing, "த");
+        // Shift+Z: ஆ, then back to the base layer
+        let edit = eng.process_key_code(44, true).unwrap();
+        assert_eq!((edit.composing.as_str(), edit.layer), ("தஆ", Layer::Base));
+        assert_eq!(eng.process_key_code(57, true).unwrap().commit, "தஆ ");
+        // Backspace deletes rather than typing its key id
+        assert_eq!(eng.process_key_code(14, false).unwrap().delete_before, 1);
+        assert_eq!(eng.get_buffer(), "தஆ");
+        // The digit row is left to the host
+        assert!(eng.process_key_code(2, false).is_none());
+    }
+
+    #[test]
+    fn test_backspace_is_a_command_only_by_key_code() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("z"); // அ
+        assert_eq!(eng.process_input("backspace").composing, "");
+        // Only the key-code mapping makes it a command
+        let edit = eng.process_event(InputEvent::KeyTap { key: "backspace".into() });
+        assert_eq!(edit.composing, "backspace");
+    }
+
+    /// A glide through the centres of `keys`
+    fn glide(eng: &Engine, keys: &[&str]) -> Vec<TouchPoint> {
+        let centres = eng.resources.layout.key_centres();
+        keys.iter().map(|k| TouchPoint { x: centres[*k].0, y: centres[*k].1 }).collect()
+    }
+
+    #[test]
+    fn test_process_gesture_commits_previous_word() {
+        let mut eng = Engine::new().unwrap();
+        let edit = eng.process_gesture(glide(&eng, &["i", "z", "k"]));
+        assert_eq!(edit.composing, "நான்");
+        assert_eq!(eng.get_gesture_alternatives()[0], "நான்");
+
+        let edit = eng.process_gesture(glide(&eng, &["u", "z", "p", "x", "g"]));
+        assert_eq!(edit.commit, "நான் ");
+        assert_eq!(edit.composing, "தமிழ்");
+        assert_eq!(eng.undo().composing, "நான்");
+    }
+
+    #[test]
+    fn test_gesture_alternatives_go_stale() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_gesture(glide(&eng, &["i", "z", "k"]));
+        eng.process_input("space");
+        assert!(eng.get_gesture_alternatives().is_empty());
+    }
+
+    #[test]
+    fn test_english_layer_types_latin() {
+        let mut eng = Engine::new().unwrap();
+        eng.process_input("english");
+        eng.process_input("h");
+        eng.process_input("i");
+        assert_eq!(eng.get_composing(), "hi");
+        eng.process_input("english");
+        assert_eq!(eng.process_input("z").composing, "hiஅ");
+    }
+
+    #[test]
+    fn test_numbers_layer() {
+        let mut eng = Engine::new().unwrap();
+        eng.set_layer(Layer::Numbers, true);
+        eng.process_input("w");
+        eng.process_input("q");
+        assert_eq!(eng.get_composing(), "21");
+        // The point after digits is a decimal point
+        assert_eq!(eng.process_input("z").composing, "21.");
+        assert_eq!(eng.get_layer(), Layer::Numbers);
+    }
+
     #[test]
     fn test_full_sentence() {
-        let mut eng = KeyboardEngine::new();
+        let mut eng = Engine::new().unwrap();
         // Type "நான் தமிழ்"
         eng.process_input("i"); // ந் pending
         eng.process_input("z"); // ந + அ -> ந... wait
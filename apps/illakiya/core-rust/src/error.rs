@@ -0,0 +1,33 @@
+//! Errors reported across the FFI boundary.
+//!
+//! Loading code returns these instead of panicking: a panic in the core
+//! takes the whole IME process down with it. UniFFI maps each variant to
+//! a Kotlin exception subclass.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IllakiyaError {
+    /// The layout JSON does not parse or describes no usable keys
+    LayoutInvalid { message: String },
+    /// The dictionary JSON does not parse
+    DictionaryCorrupt { message: String },
+    /// Persisted user data (snapshots) could not be read
+    UserDataIo { message: String },
+    /// The ONNX sandhi model could not be loaded (`SandhiModel::load`,
+    /// feature `onnx`)
+    ModelLoad { message: String },
+}
+
+impl fmt::Display for IllakiyaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IllakiyaError::LayoutInvalid { message } => write!(f, "invalid layout: {}", message),
+            IllakiyaError::DictionaryCorrupt { message } => write!(f, "corrupt dictionary: {}", message),
+            IllakiyaError::UserDataIo { message } => write!(f, "user data: {}", message),
+            IllakiyaError::ModelLoad { message } => write!(f, "model load failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for IllakiyaError {}
@@ -0,0 +1,62 @@
+//! Typed input events.
+//!
+//! Hosts describe what the user did instead of encoding it in a key
+//! string, so text such as the word "clear" can never be mistaken for a
+//! command. `process_input(key)` remains as a shim over `from_key`.
+
+use crate::gesture::TouchPoint;
+use crate::layout::{FlickDirection, Layer};
+
+/// How much a backspace removes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackspaceMode {
+    /// The pending consonant, or one character
+    Char,
+    /// The composing word, or the word before the cursor
+    Word,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// A key of the layout geometry, by key id ("q", "nedil", "space").
+    /// The layout's backspace key is sent as `Backspace`.
+    KeyTap { key: String },
+    /// A consonant key flicked towards a vowel
+    Flick { key: String, direction: FlickDirection },
+    /// A glide across the keys, normalised to the keyboard area
+    Swipe { points: Vec<TouchPoint> },
+    Backspace { mode: BackspaceMode },
+    Space,
+    Enter,
+    /// Literal text from outside the keys (clipboard, emoji, voice)
+    CommitText { text: String },
+    SetLayer { layer: Layer, locked: bool },
+    /// Drop all text state; undoable
+    Clear,
+}
+
+impl InputEvent {
+    /// Event for a key code: a legacy `process_input` key, a physical
+    /// key or the layout key under a tap. Only "backspace" and "clear"
+    /// are commands; every other string is a key tap.
+    pub fn from_key(key: &str) -> Self {
+        match key {
+            "backspace" => InputEvent::Backspace { mode: BackspaceMode::Char },
+            "clear" => InputEvent::Clear,
+            _ => InputEvent::KeyTap { key: key.to_string() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_key() {
+        assert_eq!(InputEvent::from_key("backspace"), InputEvent::Backspace { mode: BackspaceMode::Char });
+        assert_eq!(InputEvent::from_key("clear"), InputEvent::Clear);
+        assert_eq!(InputEvent::from_key("q"), InputEvent::KeyTap { key: "q".into() });
+        assert_eq!(InputEvent::from_key("space"), InputEvent::KeyTap { key: "space".into() });
+    }
+}
@@ -0,0 +1,298 @@
+//! Gesture (glide) typing over the PM0100 grid.
+//!
+//! Every dictionary word is turned into the key path a finger would
+//! glide through. PM0100 spells an uyirmei as consonant key + vowel key,
+//! so paths zig-zag between the consonant rows and the vowel row; nedil
+//! has no position of its own, so குடு and கூடு share a path and word
+//! frequency decides between them. A sampled touch path is compared with
+//! each template (both resampled to the same number of points) and
+//! candidates are ranked by path distance and frequency.
+
+use crate::dictionary::Lexicon;
+use crate::history::TextState;
+use crate::layout::LayoutDef;
+use crate::tamil;
+use std::collections::HashMap;
+
+/// Points each path is resampled to before comparison
+const SAMPLES: usize = 32;
+
+/// Path spread in key units: mean distances this large cost e^-0.5
+const GESTURE_SIGMA: f32 = 0.6;
+
+/// The path must start and end within this many keys of the word's
+/// first and last keys
+const ENDPOINT_RADIUS: f32 = 1.5;
+
+/// Weight of ln(frequency) against path distance
+const FREQ_WEIGHT: f32 = 0.5;
+
+/// A sampled touch point, normalised to 0..1 of the keyboard area
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// The last decoded gesture, kept so the host can offer the runners-up
+#[derive(Debug, Clone)]
+pub struct GestureRecord {
+    /// Text state right after; the record is stale once this changes
+    pub after: TextState,
+    /// Ranked words, the typed one first
+    pub candidates: Vec<String>,
+}
+
+struct Template {
+    word: String,
+    freq: u32,
+    /// Resampled ideal path, in key units
+    path: Vec<(f32, f32)>,
+}
+
+pub struct GestureDecoder {
+    templates: Vec<Template>,
+    /// Key units per normalised unit: widest row, number of rows
+    scale: (f32, f32),
+}
+
+impl GestureDecoder {
+    /// Build a template for every dictionary word the layout can spell
+    pub fn new(layout: &LayoutDef, lexicon: &Lexicon) -> Self {
+        let rows = &layout.geometry.rows;
+        let widest = rows.iter()
+            .map(|row| row.iter().map(|k| k.width).sum::<f32>())
+            .fold(0.0, f32::max);
+        let scale = (widest, rows.len() as f32);
+        let centres = layout.key_centres();
+        let keys = KeyIndex::new(layout);
+
+        let mut templates = Vec::new();
+        for (word, freq) in lexicon.words() {
+            let Some(path) = keys.word_keys(word) else { continue };
+            let points: Option<Vec<(f32, f32)>> = path.iter()
+                .map(|k| centres.get(k).map(|&(x, y)| (x * scale.0, y * scale.1)))
+                .collect();
+            // A word with no keys (an empty entry) has no path to match
+            if let Some(points) = points.filter(|p| !p.is_empty()) {
+                templates.push(Template { word: word.to_string(), freq, path: resample(&points, SAMPLES) });
+            }
+        }
+        Self { templates, scale }
+    }
+
+    /// Rank dictionary words for a sampled touch path, best first
+    pub fn decode(&self, points: &[TouchPoint], limit: usize) -> Vec<String> {
+        if points.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+        let path: Vec<(f32, f32)> = points.iter()
+            .map(|p| (p.x * self.scale.0, p.y * self.scale.1))
+            .collect();
+        let sampled = resample(&path, SAMPLES);
+        let (start, end) = (sampled[0], sampled[SAMPLES - 1]);
+
+        let mut scored: Vec<(&str, f32)> = self.templates.iter()
+            .filter(|t| {
+                distance(t.path[0], start) <= ENDPOINT_RADIUS
+                    && distance(t.path[SAMPLES - 1], end) <= ENDPOINT_RADIUS
+            })
+            .map(|t| {
+                let mean = t.path.iter().zip(&sampled)
+                    .map(|(&a, &b)| distance(a, b))
+                    .sum::<f32>() / SAMPLES as f32;
+                let score = -(mean * mean) / (2.0 * GESTURE_SIGMA * GESTURE_SIGMA)
+                    + FREQ_WEIGHT * (t.freq.max(1) as f32).ln();
+                (t.word.as_str(), score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let mut words: Vec<String> = Vec::new();
+        for (word, _) in scored {
+            if !words.iter().any(|w| w == word) {
+                words.push(word.to_string());
+                if words.len() == limit {
+                    break;
+                }
+            }
+        }
+        words
+    }
+}
+
+/// Reverse of the layout's letter layers: which key types each letter
+struct KeyIndex {
+    consonants: HashMap<char, String>,
+    vowels: HashMap<char, String>,
+    /// Keys typing a whole letter sequence (ஃ, ஸ்ரீ)
+    literals: Vec<(String, String)>,
+}
+
+impl KeyIndex {
+    fn new(layout: &LayoutDef) -> Self {
+        let l = &layout.layers;
+        let mut consonants = HashMap::new();
+        let mut literals = Vec::new();
+        for (key, value) in l.base.iter().chain(&l.grantha) {
+            match value.strip_suffix('்') {
+                // க்ஷ் is typed by its own key; the path cannot tell it from க்+ஷ்
+                Some(stem) if stem.chars().count() == 1 => {
+                    consonants.entry(stem.chars().next().unwrap_or_default()).or_insert(key.clone());
+                }
+                Some(_) => {}
+                None => literals.push((value.clone(), key.clone())),
+            }
+        }
+        let mut vowels = HashMap::new();
+        for (key, value) in l.vowels.iter().chain(&l.vowels_long).chain(&l.vowels_special) {
+            if let Some(v) = value.chars().next() {
+                vowels.insert(v, key.clone());
+            }
+        }
+        Self { consonants, vowels, literals }
+    }
+
+    /// Keys glided through to spell `word`, with repeats collapsed
+    /// (a glide cannot press the same key twice). None if a letter has
+    /// no key.
+    fn word_keys(&self, word: &str) -> Option<Vec<String>> {
+        let mut keys: Vec<String> = Vec::new();
+        let mut push = |key: &String| {
+            if keys.last() != Some(key) {
+                keys.push(key.clone());
+            }
+        };
+        let mut rest = word;
+        while let Some(c) = rest.chars().next() {
+            if let Some((literal, key)) = self.literals.iter().find(|(lit, _)| rest.starts_with(lit.as_str())) {
+                push(key);
+                rest = &rest[literal.len()..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+            if let Some(key) = self.consonants.get(&c) {
+                push(key);
+                // Pulli: bare mei. A vowel sign, or nothing: uyirmei
+                let vowel = match rest.chars().next() {
+                    Some(next) if tamil::is_pulli(next) => None,
+                    Some(next) if tamil::is_dependent_sign(next) => Some(tamil::vowel_for_sign(next)?),
+                    _ => Some('அ'),
+                };
+                if let Some(next) = rest.chars().next().filter(|&n| tamil::is_dependent_sign(n)) {
+                    rest = &rest[next.len_utf8()..];
+                }
+                if let Some(vowel) = vowel {
+                    push(self.vowels.get(&vowel)?);
+                }
+            } else {
+                push(self.vowels.get(&c)?);
+            }
+        }
+        Some(keys)
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+/// Resample a polyline to `n` points evenly spaced along its length.
+/// An empty polyline stays empty.
+fn resample(points: &[(f32, f32)], n: usize) -> Vec<(f32, f32)> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let total: f32 = points.windows(2).map(|w| distance(w[0], w[1])).sum();
+    if points.len() < 2 || total == 0.0 {
+        return vec![points[0]; n];
+    }
+    let step = total / (n - 1) as f32;
+    let mut out = vec![points[0]];
+    let mut carried = 0.0;
+    for w in points.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let seg = distance(a, b);
+        let mut along = step - carried;
+        while along <= seg && out.len() < n {
+            let t = along / seg;
+            out.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+            along += step;
+        }
+        carried = seg - (along - step);
+    }
+    // Rounding can leave the last point short
+    while out.len() < n {
+        out.push(points[points.len() - 1]);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(word: &str) -> Vec<String> {
+        KeyIndex::new(&LayoutDef::load_pm0100().unwrap()).word_keys(word).unwrap()
+    }
+
+    /// A glide through the centres of `keys`
+    fn glide(layout: &LayoutDef, keys: &[&str]) -> Vec<TouchPoint> {
+        let centres = layout.key_centres();
+        keys.iter().map(|k| {
+            let (x, y) = centres[*k];
+            TouchPoint { x, y }
+        }).collect()
+    }
+
+    #[test]
+    fn test_word_keys_alternate_consonant_and_vowel() {
+        assert_eq!(keys("தமிழ்"), ["u", "z", "p", "x", "g"]);
+        assert_eq!(keys("நான்"), ["i", "z", "k"]);
+        assert_eq!(keys("ஐந்து"), ["n", "i", "u", "c"]);
+    }
+
+    #[test]
+    fn test_word_keys_collapse_repeats() {
+        // அக்கா: அ க் கா -> z q z
+        assert_eq!(keys("அக்கா"), ["z", "q", "z"]);
+    }
+
+    #[test]
+    fn test_resample_spacing() {
+        let path = resample(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)], 5);
+        assert_eq!(path.len(), 5);
+        assert_eq!(path[2], (1.0, 0.0));
+        assert_eq!(path[4], (1.0, 1.0));
+    }
+
+    #[test]
+    fn test_empty_word_has_no_template() {
+        assert!(resample(&[], 5).is_empty());
+        let lexicon = Lexicon::from_json(r#"{"version": "1", "word_count": 2, "words": [
+            {"tamil": "", "freq": 10}, {"tamil": "நான்", "freq": 5}]}"#).unwrap();
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let decoder = GestureDecoder::new(&layout, &lexicon);
+        assert_eq!(decoder.decode(&glide(&layout, &["i", "z", "k"]), 5), ["நான்"]);
+    }
+
+    #[test]
+    fn test_decode_glide() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let decoder = GestureDecoder::new(&layout, &Lexicon::base().unwrap());
+        let words = decoder.decode(&glide(&layout, &["u", "z", "p", "x", "g"]), 5);
+        assert_eq!(words[0], "தமிழ்");
+        let words = decoder.decode(&glide(&layout, &["i", "z", "k"]), 5);
+        assert_eq!(words[0], "நான்");
+    }
+
+    #[test]
+    fn test_decode_rejects_far_endpoints() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let decoder = GestureDecoder::new(&layout, &Lexicon::base().unwrap());
+        // A glide on the enter key, far from every letter key
+        let path = [TouchPoint { x: 0.92, y: 0.875 }, TouchPoint { x: 0.93, y: 0.875 }];
+        assert!(decoder.decode(&path, 5).is_empty());
+    }
+}
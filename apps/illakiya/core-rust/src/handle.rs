@@ -0,0 +1,342 @@
+//! Thread-safe engine handle for UniFFI.
+//!
+//! UniFFI objects must be `Send + Sync` and are called through `&self`,
+//! possibly from several threads (the IME's UI thread, prediction or
+//! learning tasks). `KeyboardEngine` keeps the single-threaded `Engine`
+//! behind a mutex and forwards each call; see `Engine` for what the
+//! methods do. Immutable resources are shared, not copied, between
+//! handles created with `with_resources`.
+//!
+//! Construction returns as soon as the layout is parsed; the dictionary
+//! loads on a background thread (`is_dictionary_ready`, `set_ready_listener`).
+
+use crate::context::InputContext;
+use crate::edit::EditResult;
+use crate::engine::Engine;
+use crate::error::IllakiyaError;
+use crate::event::InputEvent;
+use crate::gesture::TouchPoint;
+use crate::layout::{FlickDirection, KeyboardModel, Layer};
+use crate::resources::{ReadyListener, Resources};
+use crate::touch::KeyCandidate;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+pub struct KeyboardEngine {
+    engine: Mutex<Engine>,
+}
+
+impl KeyboardEngine {
+    pub fn new() -> Result<Self, IllakiyaError> {
+        Ok(Self::loading(Resources::pm0100_layout()?))
+    }
+
+    pub fn with_layout(layout_json: &str) -> Result<Self, IllakiyaError> {
+        Ok(Self::loading(Resources::with_layout(layout_json)?))
+    }
+
+    /// A handle over already loaded resources; nothing is copied
+    pub fn with_resources(resources: Arc<Resources>) -> Self {
+        Self { engine: Mutex::new(Engine::with_resources(resources)) }
+    }
+
+    fn loading(resources: Arc<Resources>) -> Self {
+        resources.load_in_background();
+        Self::with_resources(resources)
+    }
+
+    /// Call `listener` once the dictionary has loaded: at once if it
+    /// already has, otherwise on the loading thread
+    pub fn set_ready_listener(&self, listener: Box<dyn ReadyListener>) {
+        let resources = self.lock().resources().clone();
+        resources.on_ready(move || listener.on_ready());
+    }
+
+    /// A panic while locked cannot leave the engine half-updated in a way
+    /// that matters more than losing the keyboard, so poisoning is ignored
+    fn lock(&self) -> MutexGuard<'_, Engine> {
+        self.engine.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn toggle_nedil(&self) {
+        self.lock().toggle_nedil()
+    }
+
+    pub fn toggle_grantha(&self) {
+        self.lock().toggle_grantha()
+    }
+
+    pub fn set_layer(&self, layer: Layer, locked: bool) {
+        self.lock().set_layer(layer, locked)
+    }
+
+    pub fn get_layer(&self) -> Layer {
+        self.lock().get_layer()
+    }
+
+    pub fn is_layer_locked(&self) -> bool {
+        self.lock().is_layer_locked()
+    }
+
+    pub fn get_key_labels(&self) -> HashMap<String, String> {
+        self.lock().get_key_labels()
+    }
+
+    pub fn get_keyboard_model(&self) -> KeyboardModel {
+        self.lock().get_keyboard_model()
+    }
+
+    pub fn process_event(&self, event: InputEvent) -> EditResult {
+        self.lock().process_event(event)
+    }
+
+    pub fn process_input(&self, key: &str) -> EditResult {
+        self.lock().process_input(key)
+    }
+
+    pub fn process_flick(&self, key: &str, direction: FlickDirection) -> EditResult {
+        self.lock().process_flick(key, direction)
+    }
+
+    pub fn process_key_code(&self, code: u32, shift: bool) -> Option<EditResult> {
+        self.lock().process_key_code(code, shift)
+    }
+
+    pub fn process_tap(&self, x: f32, y: f32) -> EditResult {
+        self.lock().process_tap(x, y)
+    }
+
+    pub fn process_touch(&self, candidates: Vec<KeyCandidate>) -> EditResult {
+        self.lock().process_touch(candidates)
+    }
+
+    pub fn get_tap_alternatives(&self) -> Vec<KeyCandidate> {
+        self.lock().get_tap_alternatives()
+    }
+
+    pub fn correct_last_tap(&self, key: &str) -> EditResult {
+        self.lock().correct_last_tap(key)
+    }
+
+    pub fn decode_gesture(&self, points: Vec<TouchPoint>, limit: u32) -> Vec<String> {
+        self.lock().decode_gesture(points, limit)
+    }
+
+    pub fn process_gesture(&self, points: Vec<TouchPoint>) -> EditResult {
+        self.lock().process_gesture(points)
+    }
+
+    pub fn get_gesture_alternatives(&self) -> Vec<String> {
+        self.lock().get_gesture_alternatives()
+    }
+
+    pub fn finish_composing(&self) -> EditResult {
+        self.lock().finish_composing()
+    }
+
+    pub fn get_suggestions(&self, limit: u32) -> Vec<String> {
+        self.lock().get_suggestions(limit)
+    }
+
+    pub fn get_key_probabilities(&self) -> HashMap<String, f32> {
+        self.lock().get_key_probabilities()
+    }
+
+    pub fn get_sandhi_suggestion(&self) -> Option<String> {
+        self.lock().get_sandhi_suggestion()
+    }
+
+    pub fn apply_sandhi(&self) -> EditResult {
+        self.lock().apply_sandhi()
+    }
+
+    pub fn accept_suggestion(&self, suggestion: &str) -> EditResult {
+        self.lock().accept_suggestion(suggestion)
+    }
+
+    pub fn set_surrounding_text(&self, before: &str, after: &str) -> EditResult {
+        self.lock().set_surrounding_text(before, after)
+    }
+
+    pub fn is_valid_word(&self, word: &str) -> bool {
+        self.lock().is_valid_word(word)
+    }
+
+    pub fn translate_current(&self) -> Option<String> {
+        self.lock().translate_current()
+    }
+
+    pub fn get_buffer(&self) -> String {
+        self.lock().get_buffer()
+    }
+
+    pub fn get_composing(&self) -> String {
+        self.lock().get_composing()
+    }
+
+    pub fn get_pending(&self) -> Option<String> {
+        self.lock().get_pending()
+    }
+
+    pub fn get_current_word(&self) -> String {
+        self.lock().get_current_word()
+    }
+
+    pub fn is_nedil_active(&self) -> bool {
+        self.lock().is_nedil_active()
+    }
+
+    pub fn is_grantha_active(&self) -> bool {
+        self.lock().is_grantha_active()
+    }
+
+    pub fn toggle_symbols(&self) {
+        self.lock().toggle_symbols()
+    }
+
+    pub fn is_symbols_active(&self) -> bool {
+        self.lock().is_symbols_active()
+    }
+
+    pub fn dictionary_size(&self) -> u32 {
+        self.lock().dictionary_size()
+    }
+
+    pub fn is_dictionary_ready(&self) -> bool {
+        self.lock().is_dictionary_ready()
+    }
+
+    pub fn undo(&self) -> EditResult {
+        self.lock().undo()
+    }
+
+    pub fn redo(&self) -> EditResult {
+        self.lock().redo()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.lock().can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.lock().can_redo()
+    }
+
+    pub fn snapshot(&self) -> String {
+        self.lock().snapshot()
+    }
+
+    pub fn restore(&self, blob: &str) -> Result<(), IllakiyaError> {
+        self.lock().restore(blob)
+    }
+
+    pub fn set_incognito(&self, enabled: bool) {
+        self.lock().set_incognito(enabled)
+    }
+
+    pub fn is_incognito(&self) -> bool {
+        self.lock().is_incognito()
+    }
+
+    pub fn set_input_context(&self, context: InputContext) {
+        self.lock().set_input_context(context)
+    }
+
+    pub fn get_input_context(&self) -> InputContext {
+        self.lock().get_input_context()
+    }
+
+    pub fn reset(&self) {
+        self.lock().reset()
+    }
+
+    pub fn create_session(&self, name: &str) {
+        self.lock().create_session(name)
+    }
+
+    pub fn switch_session(&self, name: &str) {
+        self.lock().switch_session(name)
+    }
+
+    pub fn destroy_session(&self, name: &str) {
+        self.lock().destroy_session(name)
+    }
+
+    pub fn active_session(&self) -> String {
+        self.lock().active_session()
+    }
+
+    pub fn session_names(&self) -> Vec<String> {
+        self.lock().session_names()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_handle_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<KeyboardEngine>();
+        assert_send_sync::<crate::dictionary::Dictionary>();
+        assert_send_sync::<crate::sandhi::AdhanSandhi>();
+    }
+
+    #[test]
+    fn test_engines_run_concurrently_on_shared_resources() {
+        let resources = Resources::pm0100().unwrap();
+        let engines: Vec<_> = (0..4).map(|_| KeyboardEngine::with_resources(resources.clone())).collect();
+        // Four engines, one copy of the layout and word list
+        assert!(Arc::strong_count(&resources) >= 5);
+        let handles: Vec<_> = engines.into_iter().map(|engine| {
+            thread::spawn(move || {
+                for key in ["u", "z", "p", "x", "g"] {
+                    engine.process_input(key);
+                }
+                engine.get_composing()
+            })
+        }).collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), "தமிழ்");
+        }
+    }
+
+    #[test]
+    fn test_types_while_dictionary_loads() {
+        struct Notify(Mutex<mpsc::Sender<()>>);
+        impl ReadyListener for Notify {
+            fn on_ready(&self) {
+                let _ = self.0.lock().unwrap().send(());
+            }
+        }
+
+        let json = include_str!("../../data/layouts/pm0100.json");
+        let engine = KeyboardEngine::with_resources(Resources::with_layout(json).unwrap());
+        assert!(!engine.is_dictionary_ready());
+        engine.process_input("u");
+        assert_eq!(engine.process_input("z").composing, "த");
+        assert!(engine.get_suggestions(5).is_empty());
+
+        let (tx, rx) = mpsc::channel();
+        engine.set_ready_listener(Box::new(Notify(Mutex::new(tx))));
+        engine.lock().resources().load_in_background();
+        rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(engine.is_dictionary_ready());
+        assert!(!engine.get_suggestions(5).is_empty());
+    }
+
+    #[test]
+    fn test_one_engine_shared_between_threads() {
+        let engine = Arc::new(KeyboardEngine::new().unwrap());
+        engine.process_input("u");
+        let reader = {
+            let engine = engine.clone();
+            thread::spawn(move || engine.get_pending())
+        };
+        assert_eq!(reader.join().unwrap(), Some("த்".to_string()));
+    }
+}
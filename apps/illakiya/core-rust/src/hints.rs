@@ -0,0 +1,129 @@
+//! Next-key hints: how likely each key is to be pressed next.
+//!
+//! Every letter key on the active layer is played forward against the
+//! composing word, and the dictionary weight of the resulting prefix is
+//! its score. Scores are normalised into probabilities, so keys that lead
+//! to no dictionary word get 0.0.
+
+use crate::dictionary::Dictionary;
+use crate::layout::{Layer, LayoutDef};
+use crate::tamil;
+use std::collections::HashMap;
+
+/// Probability per key code of being pressed next, given the composing
+/// `word` and `pending` consonant. Empty when the dictionary has nothing
+/// to say (no word continues the prefix, or a non-letter layer is active).
+pub fn next_key_probabilities(
+    layout: &LayoutDef,
+    dict: &Dictionary,
+    layer: Layer,
+    word: &str,
+    pending: Option<&str>,
+) -> HashMap<String, f32> {
+    if !matches!(layer, Layer::Base | Layer::Nedil | Layer::Grantha) {
+        return HashMap::new();
+    }
+
+    let mut prefix = String::with_capacity(word.len() + 16);
+    let weights: Vec<(String, u64)> = layout.labels(layer)
+        .into_iter()
+        .map(|(key, label)| {
+            prefix.clear();
+            prefix.push_str(word);
+            let weight = key_weight(layout, dict, pending, &label, &mut prefix);
+            (key, weight)
+        })
+        .collect();
+
+    let total: u64 = weights.iter().map(|(_, w)| w).sum();
+    if total == 0 {
+        return HashMap::new();
+    }
+    weights.into_iter()
+        .map(|(key, w)| (key, w as f32 / total as f32))
+        .collect()
+}
+
+/// Dictionary weight of typing `label` after `prefix` (the composing word)
+fn key_weight(
+    layout: &LayoutDef,
+    dict: &Dictionary,
+    pending: Option<&str>,
+    label: &str,
+    prefix: &mut String,
+) -> u64 {
+    // A consonant key leaves a pending mei, which any vowel may complete
+    if let Some(stem) = label.strip_suffix('்') {
+        if let Some(pending) = pending {
+            prefix.push_str(pending);
+        }
+        prefix.push_str(stem);
+        return dict.prefix_weight(prefix);
+    }
+
+    let is_vowel = label.chars().count() == 1 && label.chars().all(tamil::is_uyir);
+    match pending {
+        Some(mei) if is_vowel => {
+            if !layout.combine_into(mei, label, prefix) {
+                return 0;
+            }
+            // அ adds no sign: the letter must end here
+            if label == "அ" {
+                dict.letter_weight(prefix)
+            } else {
+                dict.prefix_weight(prefix)
+            }
+        }
+        Some(mei) => {
+            prefix.push_str(mei);
+            prefix.push_str(label);
+            dict.prefix_weight(prefix)
+        }
+        None => {
+            prefix.push_str(label);
+            dict.prefix_weight(prefix)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probs(word: &str, pending: Option<&str>) -> HashMap<String, f32> {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let dict = Dictionary::new().unwrap();
+        next_key_probabilities(&layout, &dict, Layer::Base, word, pending)
+    }
+
+    #[test]
+    fn test_only_vowel_after_tam() {
+        // "த" + ம் pending: every dictionary word continues with மி
+        let p = probs("த", Some("ம்"));
+        assert!((p["x"] - 1.0).abs() < 1e-6); // இ
+        assert_eq!(p["z"], 0.0);
+        assert_eq!(p["q"], 0.0);
+    }
+
+    #[test]
+    fn test_consonants_after_naa() {
+        let p = probs("நா", None);
+        assert!(p["k"] > 0.0); // நான்
+        assert!(p["k"] > p["a"]); // நான் outweighs நாய்
+        assert_eq!(p["z"], 0.0); // நாஅ
+        let sum: f32 = p.values().sum();
+        assert!((sum - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_unknown_prefix_is_empty() {
+        assert!(probs("ஞௌ", None).is_empty());
+    }
+
+    #[test]
+    fn test_symbols_layer_is_empty() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let dict = Dictionary::new().unwrap();
+        assert!(next_key_probabilities(&layout, &dict, Layer::Symbols, "", None).is_empty());
+    }
+}
@@ -0,0 +1,183 @@
+//! Undo/redo history of engine edits.
+//!
+//! Each entry stores the engine text state from *before* an operation.
+//! Consecutive key presses within a word coalesce into one entry, so
+//! undo reverts a whole typing run rather than a single akshara.
+
+/// Kind of engine operation recorded in the history
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditOp {
+    Typing,
+    AcceptSuggestion,
+    SandhiJoin,
+    Clear,
+}
+
+impl EditOp {
+    /// Operations that replace what the user typed; a backspace right
+    /// after one of these reverts it instead of deleting a character.
+    pub fn is_autocorrection(self) -> bool {
+        matches!(self, EditOp::AcceptSuggestion | EditOp::SandhiJoin)
+    }
+}
+
+/// Engine text state captured for undo
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextState {
+    pub buffer: String,
+    pub words: Vec<String>,
+    pub current_word: String,
+    pub pending_consonant: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    op: EditOp,
+    state: TextState,
+}
+
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+    max_entries: usize,
+    /// A typing run is open; further key presses coalesce into it
+    typing_run: bool,
+    /// The last operation was an autocorrection and nothing happened since
+    autocorrect_armed: bool,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            max_entries: 100,
+            typing_run: false,
+            autocorrect_armed: false,
+        }
+    }
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record an operation that changed the text state from `before`
+    pub fn record(&mut self, op: EditOp, before: TextState) {
+        self.autocorrect_armed = op.is_autocorrection();
+        if op == EditOp::Typing && self.typing_run {
+            return;
+        }
+        self.typing_run = op == EditOp::Typing;
+        self.redo.clear();
+        self.undo.push(Entry { op, state: before });
+        if self.undo.len() > self.max_entries {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Close the current typing run (word boundary)
+    pub fn end_run(&mut self) {
+        self.typing_run = false;
+    }
+
+    /// True if the last operation was an autocorrection
+    pub fn autocorrect_armed(&self) -> bool {
+        self.autocorrect_armed
+    }
+
+    /// Something other than a backspace followed the autocorrection, even
+    /// if it changed no text (a layer switch)
+    pub fn disarm(&mut self) {
+        self.autocorrect_armed = false;
+    }
+
+    /// Step back: returns the state to restore, given the current one
+    pub fn undo(&mut self, current: TextState) -> Option<TextState> {
+        let entry = self.undo.pop()?;
+        self.redo.push(Entry { op: entry.op, state: current });
+        self.typing_run = false;
+        self.autocorrect_armed = false;
+        Some(entry.state)
+    }
+
+    /// Step forward again after an undo
+    pub fn redo(&mut self, current: TextState) -> Option<TextState> {
+        let entry = self.redo.pop()?;
+        self.undo.push(Entry { op: entry.op, state: current });
+        self.typing_run = false;
+        self.autocorrect_armed = false;
+        Some(entry.state)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.typing_run = false;
+        self.autocorrect_armed = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(word: &str) -> TextState {
+        TextState { current_word: word.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_typing_coalesces() {
+        let mut h = History::new();
+        h.record(EditOp::Typing, state(""));
+        h.record(EditOp::Typing, state("த"));
+        assert_eq!(h.undo(state("தம")), Some(state("")));
+        assert!(!h.can_undo());
+    }
+
+    #[test]
+    fn test_end_run_starts_new_entry() {
+        let mut h = History::new();
+        h.record(EditOp::Typing, state(""));
+        h.end_run();
+        h.record(EditOp::Typing, state("த"));
+        assert_eq!(h.undo(state("தம")), Some(state("த")));
+        assert!(h.can_undo());
+    }
+
+    #[test]
+    fn test_redo_after_undo() {
+        let mut h = History::new();
+        h.record(EditOp::Clear, state("த"));
+        let restored = h.undo(state("")).unwrap();
+        assert_eq!(h.redo(restored), Some(state("")));
+        assert!(!h.can_redo());
+    }
+
+    #[test]
+    fn test_new_record_clears_redo() {
+        let mut h = History::new();
+        h.record(EditOp::Clear, state("த"));
+        h.undo(state(""));
+        h.record(EditOp::Typing, state("த"));
+        assert!(!h.can_redo());
+    }
+
+    #[test]
+    fn test_autocorrect_armed_until_next_op() {
+        let mut h = History::new();
+        h.record(EditOp::AcceptSuggestion, state("த"));
+        assert!(h.autocorrect_armed());
+        h.record(EditOp::Typing, state("தமிழ்"));
+        assert!(!h.autocorrect_armed());
+    }
+}
@@ -0,0 +1,71 @@
+//! Physical keyboards: which layout key a hardware key types.
+//!
+//! Desktop IMEs report keys by position, as Linux evdev codes, whatever
+//! keyboard layout the OS has configured. PM0100 names its letter keys
+//! after the US QWERTY key in the same place ("q" is the top-left
+//! letter), so the keymap is read from the layout geometry: every key id
+//! that names a QWERTY position is typed by that physical key, and a
+//! physical keyboard types exactly what the same on-screen key would.
+//! Keys with no physical counterpart (the layer shifts) are reached
+//! through modifiers: Shift stands in for the nedil key.
+
+use crate::layout::LayoutDef;
+use std::collections::{HashMap, HashSet};
+
+/// evdev codes of the US QWERTY keys a layout may name
+const QWERTY: [(u32, &str); 29] = [
+    (16, "q"), (17, "w"), (18, "e"), (19, "r"), (20, "t"),
+    (21, "y"), (22, "u"), (23, "i"), (24, "o"), (25, "p"),
+    (30, "a"), (31, "s"), (32, "d"), (33, "f"), (34, "g"),
+    (35, "h"), (36, "j"), (37, "k"), (38, "l"),
+    (44, "z"), (45, "x"), (46, "c"), (47, "v"), (48, "b"),
+    (49, "n"), (50, "m"),
+    (57, "space"), (14, "backspace"), (28, "enter"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    keys: HashMap<u32, String>,
+}
+
+impl Keymap {
+    pub fn new(layout: &LayoutDef) -> Self {
+        let ids: HashSet<&str> = layout.geometry.rows.iter()
+            .flatten()
+            .map(|k| k.key.as_str())
+            .collect();
+        let keys = QWERTY.iter()
+            .filter(|(_, id)| ids.contains(id))
+            .map(|&(code, id)| (code, id.to_string()))
+            .collect();
+        Self { keys }
+    }
+
+    /// Layout key id at evdev `code`, or None if the layout has no key
+    /// there (digits, punctuation, navigation)
+    pub fn key(&self, code: u32) -> Option<&str> {
+        self.keys.get(&code).map(String::as_str)
+    }
+
+    /// True for letter keys, which Shift moves to the nedil layer
+    pub fn is_letter(key: &str) -> bool {
+        key.len() == 1 && key.bytes().all(|b| b.is_ascii_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pm0100_keymap() {
+        let keymap = Keymap::new(&LayoutDef::load_pm0100().unwrap());
+        assert_eq!(keymap.key(16), Some("q"));
+        assert_eq!(keymap.key(50), Some("m"));
+        assert_eq!(keymap.key(57), Some("space"));
+        assert_eq!(keymap.key(14), Some("backspace"));
+        // The digit row has no PM0100 key
+        assert_eq!(keymap.key(2), None);
+        assert!(Keymap::is_letter("q") && !Keymap::is_letter("space"));
+    }
+}
@@ -1,13 +1,59 @@
-use serde::Deserialize;
+use crate::error::IllakiyaError;
+use crate::tamil;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+/// Named key layer. Nedil and grantha are one-shot shifts (they apply
+/// to the next key); the others stay locked until switched off.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Layer {
+    #[default]
+    Base,
+    Nedil,
+    Grantha,
+    Symbols,
+    Numbers,
+    English,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 6] = [
+        Layer::Base, Layer::Nedil, Layer::Grantha,
+        Layer::Symbols, Layer::Numbers, Layer::English,
+    ];
+
+    /// Name used for the layer's shift key ("nedil", "symbols", ...)
+    pub fn name(self) -> &'static str {
+        match self {
+            Layer::Base => "base",
+            Layer::Nedil => "nedil",
+            Layer::Grantha => "grantha",
+            Layer::Symbols => "symbols",
+            Layer::Numbers => "numbers",
+            Layer::English => "english",
+        }
+    }
+
+    pub fn is_one_shot(self) -> bool {
+        matches!(self, Layer::Nedil | Layer::Grantha)
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct LayoutDef {
     pub name: String,
     pub version: String,
     pub total_characters: u32,
     pub layers: Layers,
+    #[serde(default)]
+    pub geometry: Geometry,
+    #[serde(default)]
+    pub flick: Flick,
     pub modifiers: Modifiers,
+    /// `modifiers.combinations` indexed by consonant, then vowel
+    #[serde(skip)]
+    overrides: HashMap<String, HashMap<String, String>>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -16,19 +62,147 @@ pub struct Layers {
     pub vowels: HashMap<String, String>,
     pub vowels_long: HashMap<String, String>,
     pub vowels_special: HashMap<String, String>,
+    /// Grantha consonants (ஜ ஷ ஸ ஹ க்ஷ) and ஸ்ரீ, reached via long press
+    #[serde(default)]
+    pub grantha: HashMap<String, String>,
+    /// Tamil numerals (௦-௯, ௰ ௱ ௲) and calendar/accounting signs
+    #[serde(default)]
+    pub symbols: HashMap<String, String>,
+    /// ASCII digits and punctuation
+    #[serde(default)]
+    pub numbers: HashMap<String, String>,
+}
+
+/// Physical key arrangement, shared by every layer
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Geometry {
+    pub rows: Vec<Vec<KeyGeometry>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct KeyGeometry {
+    /// Key id passed to `process_input`
+    pub key: String,
+    /// Fixed label for function keys; letter keys take theirs from the
+    /// active layer
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Width in key units (a letter key is 1.0)
+    #[serde(default = "default_key_width")]
+    pub width: f32,
+    /// Layer whose letter the long-press popup offers
+    #[serde(default)]
+    pub popup: Option<Layer>,
+}
+
+fn default_key_width() -> f32 {
+    1.0
+}
+
+/// Direction of a flick on a consonant key
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FlickDirection {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl FlickDirection {
+    /// Counter-clockwise from right, as returned by `atan2`
+    const BY_ANGLE: [FlickDirection; 8] = [
+        FlickDirection::Right, FlickDirection::UpRight, FlickDirection::Up, FlickDirection::UpLeft,
+        FlickDirection::Left, FlickDirection::DownLeft, FlickDirection::Down, FlickDirection::DownRight,
+    ];
+
+    /// Direction of a drag by (`dx`, `dy`) in screen coordinates (y grows
+    /// downwards), snapped to the nearest of the eight
+    pub fn from_delta(dx: f32, dy: f32) -> Self {
+        let octant = (-dy).atan2(dx) / std::f32::consts::FRAC_PI_4;
+        Self::BY_ANGLE[(octant.round() as i32).rem_euclid(8) as usize]
+    }
+}
+
+/// Flick input: a consonant key flicked in a direction types the
+/// uyirmei with that direction's vowel
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct Flick {
+    /// Vowel per direction, shared by every consonant key
+    #[serde(default)]
+    pub directions: HashMap<FlickDirection, String>,
+    /// Per-key overrides of `directions`
+    #[serde(default)]
+    pub keys: HashMap<String, HashMap<FlickDirection, String>>,
+}
+
+/// One key as a front-end should draw it
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyModel {
+    /// Key id to send to `process_input`
+    pub key: String,
+    /// Main label for the active layer
+    pub primary: String,
+    /// Small label, also offered in the long-press popup
+    pub secondary: Option<String>,
+    /// Layer to switch to (one-shot) when the popup is chosen
+    pub popup_layer: Option<Layer>,
+    pub width: f32,
+    /// False when the key cannot follow the pending consonant
+    pub valid: bool,
+}
+
+/// Renderable keyboard for one layer and engine state
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardModel {
+    pub layer: Layer,
+    pub locked: bool,
+    pub rows: Vec<Vec<KeyModel>>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct Modifiers {
     pub nedil_shift: String,
+    #[serde(default)]
+    pub grantha_shift: String,
+    #[serde(default)]
+    pub symbols_shift: String,
+    #[serde(default)]
+    pub numbers_shift: String,
+    #[serde(default)]
+    pub english_shift: String,
+    /// Uyirmei overrides keyed "க்+அ". Normally empty: every combination
+    /// is composed from Unicode by `tamil::compose_into`.
+    #[serde(default)]
     pub combinations: HashMap<String, String>,
 }
 
 impl LayoutDef {
     /// Load layout from embedded JSON string (zero filesystem dependency)
-    pub fn load_pm0100() -> Self {
-        let json = include_str!("../../data/layouts/pm0100.json");
-        serde_json::from_str(json).expect("Invalid pm0100.json")
+    pub fn load_pm0100() -> Result<Self, IllakiyaError> {
+        Self::from_json(include_str!("../../data/layouts/pm0100.json"))
+    }
+
+    /// Parse a layout definition (PM0100 or a custom layout)
+    pub fn from_json(json: &str) -> Result<Self, IllakiyaError> {
+        let mut layout: Self = serde_json::from_str(json)
+            .map_err(|e| IllakiyaError::LayoutInvalid { message: e.to_string() })?;
+        if layout.layers.base.is_empty() || layout.layers.vowels.is_empty() {
+            return Err(IllakiyaError::LayoutInvalid { message: "no consonant or vowel keys".into() });
+        }
+        for (combo, result) in &layout.modifiers.combinations {
+            if let Some((consonant, vowel)) = combo.split_once('+') {
+                layout.overrides
+                    .entry(consonant.to_string())
+                    .or_default()
+                    .insert(vowel.to_string(), result.clone());
+            }
+        }
+        Ok(layout)
     }
 
     /// Lookup a key in the base consonant layer
@@ -51,6 +225,141 @@ impl LayoutDef {
         self.layers.vowels_special.get(key)
     }
 
+    /// Lookup a grantha letter (long press)
+    pub fn grantha_lookup(&self, key: &str) -> Option<&String> {
+        self.layers.grantha.get(key)
+    }
+
+    /// Lookup a numeral or sign in the symbols layer
+    pub fn symbols_lookup(&self, key: &str) -> Option<&String> {
+        self.layers.symbols.get(key)
+    }
+
+    /// Lookup a key in a table layer (grantha, symbols, numbers).
+    /// Base, nedil and English resolve through the letter layers instead.
+    pub fn layer_lookup(&self, layer: Layer, key: &str) -> Option<&String> {
+        match layer {
+            Layer::Grantha => self.grantha_lookup(key),
+            Layer::Symbols => self.symbols_lookup(key),
+            Layer::Numbers => self.layers.numbers.get(key),
+            Layer::Base | Layer::Nedil | Layer::English => None,
+        }
+    }
+
+    /// Layer switched by a shift key: its name ("symbols") or the
+    /// gesture configured in `modifiers` ("swipe_up")
+    pub fn shift_layer(&self, key: &str) -> Option<Layer> {
+        let m = &self.modifiers;
+        Layer::ALL.into_iter().find(|&layer| {
+            let gesture = match layer {
+                Layer::Base => return false,
+                Layer::Nedil => &m.nedil_shift,
+                Layer::Grantha => &m.grantha_shift,
+                Layer::Symbols => &m.symbols_shift,
+                Layer::Numbers => &m.numbers_shift,
+                Layer::English => &m.english_shift,
+            };
+            key == layer.name() || (!gesture.is_empty() && key == gesture)
+        })
+    }
+
+    /// Key → label map for rendering `layer`
+    pub fn labels(&self, layer: Layer) -> HashMap<String, String> {
+        let l = &self.layers;
+        match layer {
+            Layer::Symbols => return l.symbols.clone(),
+            Layer::Numbers => return l.numbers.clone(),
+            _ => {}
+        }
+        let vowels = if layer == Layer::Nedil { &l.vowels_long } else { &l.vowels };
+        let mut labels: HashMap<String, String> = l.base.iter()
+            .chain(vowels)
+            .chain(&l.vowels_special)
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        match layer {
+            Layer::Grantha => labels.extend(l.grantha.clone()),
+            Layer::English => labels.iter_mut().for_each(|(k, v)| *v = k.clone()),
+            _ => {}
+        }
+        labels
+    }
+
+    /// Build the renderable keyboard for `layer`. With a `pending`
+    /// consonant, letter keys that cannot follow it are marked invalid.
+    pub fn keyboard_model(&self, layer: Layer, locked: bool, pending: Option<&str>) -> KeyboardModel {
+        let labels = self.labels(layer);
+        let letters = matches!(layer, Layer::Base | Layer::Nedil | Layer::Grantha);
+        let pending = pending
+            .and_then(|p| p.strip_suffix('்'))
+            .and_then(|stem| stem.chars().last());
+
+        let rows = self.geometry.rows.iter().map(|row| {
+            row.iter().map(|g| {
+                let primary = labels.get(&g.key).or(g.label.as_ref())
+                    .cloned()
+                    .unwrap_or_else(|| g.key.clone());
+                let secondary = g.popup
+                    .filter(|_| letters)
+                    .and_then(|popup| self.popup_lookup(popup, &g.key))
+                    .filter(|label| **label != primary)
+                    .cloned();
+                let valid = match pending {
+                    Some(mei) if letters => can_follow_mei(mei, &primary),
+                    _ => true,
+                };
+                KeyModel {
+                    key: g.key.clone(),
+                    popup_layer: secondary.as_ref().and(g.popup),
+                    primary,
+                    secondary,
+                    width: g.width,
+                    valid,
+                }
+            }).collect()
+        }).collect();
+
+        KeyboardModel { layer, locked, rows }
+    }
+
+    /// Vowel typed by flicking `key` towards `direction`. With `long`
+    /// (nedil shift) a kuril becomes its nedil, as on the vowel row.
+    pub fn flick_vowel(&self, key: &str, direction: FlickDirection, long: bool) -> Option<&String> {
+        let vowel = self.flick.keys.get(key)
+            .and_then(|dirs| dirs.get(&direction))
+            .or_else(|| self.flick.directions.get(&direction))?;
+        if !long {
+            return Some(vowel);
+        }
+        let vowel_key = self.layers.vowels.iter().find(|(_, v)| *v == vowel).map(|(k, _)| k);
+        Some(vowel_key.and_then(|k| self.layers.vowels_long.get(k)).unwrap_or(vowel))
+    }
+
+    /// Centre of every key, normalised to 0..1 of the keyboard area.
+    /// Rows are stretched to the full width, as front-ends render them.
+    pub fn key_centres(&self) -> HashMap<String, (f32, f32)> {
+        let rows = &self.geometry.rows;
+        let mut centres = HashMap::new();
+        for (r, row) in rows.iter().enumerate() {
+            let row_width: f32 = row.iter().map(|k| k.width).sum();
+            let y = (r as f32 + 0.5) / rows.len() as f32;
+            let mut left = 0.0;
+            for key in row {
+                centres.insert(key.key.clone(), ((left + key.width / 2.0) / row_width, y));
+                left += key.width;
+            }
+        }
+        centres
+    }
+
+    /// Letter offered by a long-press popup on `layer`
+    fn popup_lookup(&self, layer: Layer, key: &str) -> Option<&String> {
+        match layer {
+            Layer::Nedil => self.vowel_long_lookup(key),
+            _ => self.layer_lookup(layer, key),
+        }
+    }
+
     /// Resolve any vowel from all layers
     pub fn any_vowel_lookup(&self, key: &str, is_long: bool) -> Option<&String> {
         if is_long {
@@ -61,10 +370,32 @@ impl LayoutDef {
         }
     }
 
-    /// Attempt consonant+vowel combination (216 entries)
-    pub fn combine(&self, consonant: &str, vowel: &str) -> Option<&String> {
-        let combo_key = format!("{}+{}", consonant, vowel);
-        self.modifiers.combinations.get(&combo_key)
+    /// Combine consonant+vowel into uyirmei, appending it to `out`.
+    /// Layout overrides win over Unicode composition. Returns false,
+    /// leaving `out` untouched, if the pair does not combine.
+    pub fn combine_into(&self, consonant: &str, vowel: &str, out: &mut String) -> bool {
+        if let Some(combined) = self.overrides.get(consonant).and_then(|v| v.get(vowel)) {
+            out.push_str(combined);
+            return true;
+        }
+        tamil::compose_into(consonant, vowel, out)
+    }
+
+    /// Attempt consonant+vowel combination
+    pub fn combine(&self, consonant: &str, vowel: &str) -> Option<String> {
+        let mut out = String::new();
+        self.combine_into(consonant, vowel, &mut out).then_some(out)
+    }
+}
+
+/// Can the key labelled `label` follow the pending mei `mei`? Vowels
+/// always can; consonants follow the mei mayakkam rules, which grantha
+/// letters (க்ஷ included) are exempt from; ஃ never can.
+fn can_follow_mei(mei: char, label: &str) -> bool {
+    match label.strip_suffix('்').filter(|stem| !stem.is_empty()) {
+        Some(stem) if stem.chars().any(tamil::is_grantha) => true,
+        Some(stem) => stem.chars().next().is_some_and(|next| tamil::can_follow(mei, next)),
+        None => label != "ஃ",
     }
 }
 
@@ -74,29 +405,234 @@ mod tests {
 
     #[test]
     fn test_load_pm0100() {
-        let layout = LayoutDef::load_pm0100();
+        let layout = LayoutDef::load_pm0100().unwrap();
         assert_eq!(layout.name, "pm0100");
-        assert_eq!(layout.total_characters, 247);
-        assert_eq!(layout.modifiers.combinations.len(), 216);
+        // 12 vowels, 18 + 5 grantha consonants with their 12 uyirmei
+        // each, ஃ and ஸ்ரீ
+        assert_eq!(layout.total_characters, 12 + 23 * 13 + 2);
+        assert!(layout.modifiers.combinations.is_empty());
     }
 
     #[test]
     fn test_all_consonants_present() {
-        let layout = LayoutDef::load_pm0100();
+        let layout = LayoutDef::load_pm0100().unwrap();
         assert_eq!(layout.layers.base.len(), 19); // 18 mei + ayutham
     }
 
     #[test]
     fn test_combine_ka() {
-        let layout = LayoutDef::load_pm0100();
+        let layout = LayoutDef::load_pm0100().unwrap();
         assert_eq!(layout.combine("க்", "அ").unwrap(), "க");
         assert_eq!(layout.combine("க்", "ஆ").unwrap(), "கா");
         assert_eq!(layout.combine("க்", "ஐ").unwrap(), "கை");
     }
 
+    #[test]
+    fn test_grantha_layer() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        assert_eq!(layout.grantha_lookup("j").unwrap(), "ஜ்");
+        assert_eq!(layout.grantha_lookup("l").unwrap(), "ஸ்ரீ");
+        assert_eq!(layout.layers.grantha.len(), 6);
+    }
+
+    #[test]
+    fn test_symbols_layer() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        assert_eq!(layout.symbols_lookup("q").unwrap(), "௧");
+        assert_eq!(layout.symbols_lookup("p").unwrap(), "௦");
+        assert_eq!(layout.symbols_lookup("d").unwrap(), "௲");
+        assert_eq!(layout.symbols_lookup("z").unwrap(), "௹");
+        assert_eq!(layout.layers.symbols.len(), 21);
+    }
+
+    #[test]
+    fn test_shift_layer() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        assert_eq!(layout.shift_layer("nedil"), Some(Layer::Nedil));
+        assert_eq!(layout.shift_layer("swipe_up"), Some(Layer::Nedil));
+        assert_eq!(layout.shift_layer("long_press"), Some(Layer::Grantha));
+        assert_eq!(layout.shift_layer("english"), Some(Layer::English));
+        assert_eq!(layout.shift_layer("base"), None);
+        assert_eq!(layout.shift_layer("q"), None);
+    }
+
+    #[test]
+    fn test_layer_labels() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let base = layout.labels(Layer::Base);
+        assert_eq!(base.len(), 26);
+        assert_eq!(base["z"], "அ");
+        assert_eq!(base["q"], "க்");
+        assert_eq!(layout.labels(Layer::Nedil)["z"], "ஆ");
+        assert_eq!(layout.labels(Layer::Grantha)["j"], "ஜ்");
+        assert_eq!(layout.labels(Layer::Grantha)["q"], "க்ஷ்");
+        assert_eq!(layout.labels(Layer::Symbols)["w"], "௨");
+        assert_eq!(layout.labels(Layer::Numbers)["w"], "2");
+        assert_eq!(layout.labels(Layer::English)["q"], "q");
+    }
+
+    #[test]
+    fn test_keyboard_model_rows() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let model = layout.keyboard_model(Layer::Base, false, None);
+        assert_eq!(model.rows.len(), 4);
+        assert_eq!(model.rows[0].len(), 10);
+        let q = &model.rows[0][0];
+        assert_eq!((q.key.as_str(), q.primary.as_str()), ("q", "க்"));
+        assert_eq!(q.secondary.as_deref(), Some("க்ஷ்"));
+        assert_eq!(q.popup_layer, Some(Layer::Grantha));
+        let space = model.rows[3].iter().find(|k| k.key == "space").unwrap();
+        assert_eq!((space.primary.as_str(), space.width), ("␣", 5.0));
+        assert!(model.rows.iter().flatten().all(|k| k.valid));
+    }
+
+    #[test]
+    fn test_flick_vowel() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        assert_eq!(layout.flick_vowel("q", FlickDirection::Up, false).unwrap(), "அ");
+        assert_eq!(layout.flick_vowel("q", FlickDirection::UpRight, true).unwrap(), "ஈ");
+        // Nedil and ஐ/ஔ have no longer form
+        assert_eq!(layout.flick_vowel("q", FlickDirection::UpLeft, true).unwrap(), "ஆ");
+        assert_eq!(layout.flick_vowel("q", FlickDirection::DownLeft, true).unwrap(), "ஐ");
+    }
+
+    #[test]
+    fn test_flick_key_override() {
+        let mut layout = LayoutDef::load_pm0100().unwrap();
+        layout.flick.keys.insert("q".into(), HashMap::from([(FlickDirection::Up, "ஓ".to_string())]));
+        assert_eq!(layout.flick_vowel("q", FlickDirection::Up, false).unwrap(), "ஓ");
+        assert_eq!(layout.flick_vowel("w", FlickDirection::Up, false).unwrap(), "அ");
+        assert_eq!(layout.flick_vowel("q", FlickDirection::Right, false).unwrap(), "உ");
+    }
+
+    #[test]
+    fn test_flick_direction_from_delta() {
+        assert_eq!(FlickDirection::from_delta(0.0, -10.0), FlickDirection::Up);
+        assert_eq!(FlickDirection::from_delta(10.0, 1.0), FlickDirection::Right);
+        assert_eq!(FlickDirection::from_delta(-7.0, 7.0), FlickDirection::DownLeft);
+        assert_eq!(FlickDirection::from_delta(-10.0, 0.5), FlickDirection::Left);
+    }
+
+    #[test]
+    fn test_key_centres() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let centres = layout.key_centres();
+        let (x, y) = centres["q"];
+        assert!((x - 0.05).abs() < 1e-6 && (y - 0.125).abs() < 1e-6);
+        let (x, _) = centres["space"];
+        assert!((x - 0.6).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_keyboard_model_nedil_layer() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let model = layout.keyboard_model(Layer::Nedil, false, None);
+        let z = model.rows[2].iter().find(|k| k.key == "z").unwrap();
+        assert_eq!(z.primary, "ஆ");
+        assert_eq!(z.secondary, None);
+        let symbols = layout.keyboard_model(Layer::Symbols, true, None);
+        assert_eq!(symbols.rows[0][0].primary, "௧");
+        assert_eq!(symbols.rows[0][0].secondary, None);
+    }
+
+    #[test]
+    fn test_keyboard_model_valid_after_pending() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let model = layout.keyboard_model(Layer::Base, false, Some("ங்"));
+        let valid = |key: &str| model.rows.iter().flatten().find(|k| k.key == key).unwrap().valid;
+        assert!(valid("q")); // ங்க
+        assert!(valid("w")); // ங்ங
+        assert!(!valid("u")); // ங்த
+        assert!(!valid("l")); // ஃ
+        assert!(valid("z")); // ஙா
+        assert!(valid("space"));
+    }
+
+    #[test]
+    fn test_keyboard_model_allows_loanword_clusters() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let valid = |model: &KeyboardModel, key: &str| {
+            model.rows.iter().flatten().find(|k| k.key == key).unwrap().valid
+        };
+        // ஸ்டாலின், ஸ்தாபனம்: any consonant may follow a grantha mei
+        let model = layout.keyboard_model(Layer::Base, false, Some("ஸ்"));
+        assert!(valid(&model, "t")); // ஸ்ட
+        assert!(valid(&model, "u")); // ஸ்த
+        let model = layout.keyboard_model(Layer::Base, false, Some("க்ஷ்"));
+        assert!(valid(&model, "y")); // க்ஷ்ண
+        // லக்ஷ்மி, and grantha letters after a Tamil mei
+        let model = layout.keyboard_model(Layer::Grantha, false, Some("ல்"));
+        assert!(valid(&model, "q")); // ல்க்ஷ
+        assert!(valid(&model, "s")); // ல்ஸ
+        let model = layout.keyboard_model(Layer::Grantha, false, Some("ம்"));
+        assert!(valid(&model, "q")); // ம்க்ஷ
+    }
+
+    #[test]
+    fn test_combine_grantha() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let vowels = ["அ","ஆ","இ","ஈ","உ","ஊ","எ","ஏ","ஐ","ஒ","ஓ","ஔ"];
+        for c in ["ஜ்", "ஷ்", "ஸ்", "ஹ்", "க்ஷ்"] {
+            for v in vowels {
+                assert!(layout.combine(c, v).is_some(), "Missing combo: {}+{}", c, v);
+            }
+        }
+        assert_eq!(layout.combine("ஹ்", "ஓ").unwrap(), "ஹோ");
+        assert_eq!(layout.combine("க்ஷ்", "அ").unwrap(), "க்ஷ");
+    }
+
+    #[test]
+    fn test_combine_every_consonant() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let vowels = ["அ","ஆ","இ","ஈ","உ","ஊ","எ","ஏ","ஐ","ஒ","ஓ","ஔ"];
+        let mut seen = std::collections::HashSet::new();
+        for c in layout.layers.base.values().filter(|c| *c != "ஃ") {
+            for v in vowels {
+                seen.insert(layout.combine(c, v).expect("missing combination"));
+            }
+        }
+        assert_eq!(seen.len(), 216);
+        assert_eq!(layout.combine("ட்", "ஈ").unwrap(), "டீ");
+        assert_eq!(layout.combine("ன்", "ஒ").unwrap(), "னொ");
+    }
+
+    #[test]
+    fn test_combine_rejects_non_pairs() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        assert_eq!(layout.combine("ஃ", "அ"), None);
+        assert_eq!(layout.combine("க", "ஆ"), None);
+        assert_eq!(layout.combine("க்", "க்"), None);
+    }
+
+    #[test]
+    fn test_combine_into_appends() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let mut word = String::from("த");
+        assert!(layout.combine_into("ம்", "இ", &mut word));
+        assert!(!layout.combine_into("ஃ", "இ", &mut word));
+        assert_eq!(word, "தமி");
+    }
+
+    #[test]
+    fn test_combination_overrides() {
+        let json = include_str!("../../data/layouts/pm0100.json")
+            .replace(r#""combinations": {}"#, r#""combinations": {"ஸ்+ஈ": "ஸ்ரீ"}"#);
+        let layout = LayoutDef::from_json(&json).unwrap();
+        assert_eq!(layout.combine("ஸ்", "ஈ").unwrap(), "ஸ்ரீ");
+        assert_eq!(layout.combine("ஸ்", "ஆ").unwrap(), "ஸா");
+    }
+
+    #[test]
+    fn test_from_json_errors() {
+        assert!(matches!(LayoutDef::from_json("{"), Err(IllakiyaError::LayoutInvalid { .. })));
+        let json = include_str!("../../data/layouts/pm0100.json")
+            .replacen(r#""vowels": {"#, r#""vowels": {}, "unused": {"#, 1);
+        assert!(matches!(LayoutDef::from_json(&json), Err(IllakiyaError::LayoutInvalid { .. })));
+    }
+
     #[test]
     fn test_combine_all_vowels_for_one_consonant() {
-        let layout = LayoutDef::load_pm0100();
+        let layout = LayoutDef::load_pm0100().unwrap();
         let vowels = ["அ","ஆ","இ","ஈ","உ","ஊ","எ","ஏ","ஐ","ஒ","ஓ","ஔ"];
         for v in vowels {
             assert!(layout.combine("த்", v).is_some(), "Missing combo: த்+{}", v);
@@ -1,3 +1,4 @@
+#[cfg(not(target_arch = "wasm32"))]
 uniffi::include_scaffolding!("illakiya");
 
 pub mod tamil;
@@ -5,7 +6,43 @@ pub mod layout;
 pub mod dictionary;
 pub mod sandhi;
 pub mod engine;
+pub mod edit;
+pub mod history;
+pub mod snapshot;
+pub mod session;
+pub mod context;
+pub mod numerals;
+pub mod hints;
+pub mod touch;
+pub mod gesture;
+pub mod event;
+pub mod error;
+pub mod resources;
+pub mod handle;
+pub mod keymap;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod capi;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
 
-pub use engine::KeyboardEngine;
+pub use handle::KeyboardEngine;
 pub use sandhi::AdhanSandhi;
 pub use dictionary::Dictionary;
+pub use edit::EditResult;
+pub use context::{InputContext, InputKind};
+pub use layout::{FlickDirection, KeyModel, KeyboardModel, Layer};
+pub use touch::KeyCandidate;
+pub use gesture::TouchPoint;
+pub use event::{BackspaceMode, InputEvent};
+pub use error::IllakiyaError;
+pub use resources::ReadyListener;
+
+/// Render a number in Tamil numerals, positional (௨௦௨௪) or
+/// traditional (௨௲௨௰௪)
+pub fn tamil_number(value: u64, traditional: bool) -> String {
+    if traditional {
+        numerals::to_traditional(value)
+    } else {
+        numerals::to_positional(value)
+    }
+}
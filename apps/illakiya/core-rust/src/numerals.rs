@@ -0,0 +1,144 @@
+//! Tamil numerals and number conversion.
+//!
+//! Tamil has its own digits (௦-௯) and, traditionally, signs for ten,
+//! hundred and thousand (௰ ௱ ௲) used multiplicatively and additively
+//! instead of place value: 1947 = ௲௯௱௪௰௭.
+
+use crate::tamil;
+
+pub const TEN: char = '௰';
+pub const HUNDRED: char = '௱';
+pub const THOUSAND: char = '௲';
+
+/// Calendar and accounting signs
+pub const DAY: char = '௳';
+pub const MONTH: char = '௴';
+pub const YEAR: char = '௵';
+pub const DEBIT: char = '௶';
+pub const CREDIT: char = '௷';
+pub const AS_ABOVE: char = '௸';
+pub const RUPEE: char = '௹';
+pub const NUMBER: char = '௺';
+
+/// Positional notation with Tamil digits: 2024 -> ௨௦௨௪
+pub fn to_positional(n: u64) -> String {
+    tamil::to_tamil_digits(&n.to_string())
+}
+
+/// Traditional notation with ௰/௱/௲: 2024 -> ௨௲௨௰௪.
+/// A multiplier of one is left out (௱ rather than ௧௱), and amounts of
+/// a thousand or more nest: 100000 -> ௱௲, 1000000 -> ௲௲.
+pub fn to_traditional(n: u64) -> String {
+    let mut out = String::new();
+    if n == 0 {
+        out.push_str(&to_positional(0));
+    } else {
+        push_traditional(n, &mut out);
+    }
+    out
+}
+
+fn push_traditional(n: u64, out: &mut String) {
+    let thousands = n / 1000;
+    if thousands > 0 {
+        if thousands > 1 {
+            push_traditional(thousands, out);
+        }
+        out.push(THOUSAND);
+    }
+    let rest = n % 1000;
+    for (unit, sign) in [(100, HUNDRED), (10, TEN)] {
+        let count = rest / unit % 10;
+        if count > 1 {
+            out.push(digit(count));
+        }
+        if count > 0 {
+            out.push(sign);
+        }
+    }
+    let units = rest % 10;
+    if units > 0 {
+        out.push(digit(units));
+    }
+}
+
+fn digit(d: u64) -> char {
+    tamil::to_tamil_digits(&d.to_string()).chars().next().unwrap_or('௦')
+}
+
+/// A run of ASCII digits
+pub fn is_integer(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_digit())
+}
+
+/// ASCII digits with at most one decimal point after them: 3, 3.5, 3.
+pub fn is_number(s: &str) -> bool {
+    match s.split_once('.') {
+        Some((whole, fraction)) => is_integer(whole) && fraction.chars().all(|c| c.is_ascii_digit()),
+        None => is_integer(s),
+    }
+}
+
+/// Suggestion-strip candidates for a typed number: positional first,
+/// then traditional when it differs. The traditional signs only count
+/// whole amounts, so a number with a decimal point gets positional
+/// digits alone (3.5 -> ௩.௫). Empty if `number` is not a number.
+pub fn suggestions(number: &str) -> Vec<String> {
+    if !is_number(number) {
+        return Vec::new();
+    }
+    // Keep leading zeros (PINs, phone numbers) in the positional form
+    let mut out = vec![tamil::to_tamil_digits(number)];
+    if let Ok(n) = number.parse::<u64>() {
+        let traditional = to_traditional(n);
+        if !out.contains(&traditional) {
+            out.push(traditional);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positional() {
+        assert_eq!(to_positional(0), "௦");
+        assert_eq!(to_positional(2024), "௨௦௨௪");
+    }
+
+    #[test]
+    fn test_traditional() {
+        assert_eq!(to_traditional(7), "௭");
+        assert_eq!(to_traditional(10), "௰");
+        assert_eq!(to_traditional(11), "௰௧");
+        assert_eq!(to_traditional(20), "௨௰");
+        assert_eq!(to_traditional(100), "௱");
+        assert_eq!(to_traditional(1947), "௲௯௱௪௰௭");
+        assert_eq!(to_traditional(2024), "௨௲௨௰௪");
+    }
+
+    #[test]
+    fn test_traditional_nests_thousands() {
+        assert_eq!(to_traditional(10_000), "௰௲");
+        assert_eq!(to_traditional(100_000), "௱௲");
+        assert_eq!(to_traditional(1_000_000), "௲௲");
+    }
+
+    #[test]
+    fn test_suggestions() {
+        assert_eq!(suggestions("2024"), vec!["௨௦௨௪", "௨௲௨௰௪"]);
+        assert_eq!(suggestions("5"), vec!["௫"]);
+        assert_eq!(suggestions("007"), vec!["௦௦௭", "௭"]);
+        assert!(suggestions("12a").is_empty());
+    }
+
+    #[test]
+    fn test_decimal_suggestions() {
+        assert_eq!(suggestions("3.5"), vec!["௩.௫"]);
+        assert_eq!(suggestions("3."), vec!["௩."]);
+        assert!(suggestions(".5").is_empty());
+        assert!(suggestions("1.2.3").is_empty());
+    }
+}
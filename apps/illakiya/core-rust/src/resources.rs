@@ -0,0 +1,191 @@
+//! Immutable resources shared by every engine.
+//!
+//! The layout, the base word list and the glide templates built from
+//! them never change after loading, so one copy lives behind an `Arc`
+//! and each engine (one per IME instance, plus background tasks) holds a
+//! reference. Per-engine state — sessions, recency, sandhi corrections —
+//! stays in the engine.
+//!
+//! The layout is small and parsed up front, so typing works as soon as an
+//! engine exists. The word list and glide templates (the slow part of a
+//! cold start) can load on a background thread; until they are ready the
+//! engine types without suggestions, key hints or glides. Sandhi rules
+//! are code and need no loading.
+
+use crate::dictionary::{Lexicon, LexiconCell};
+use crate::error::IllakiyaError;
+use crate::gesture::GestureDecoder;
+use crate::keymap::Keymap;
+use crate::layout::LayoutDef;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Once, OnceLock, PoisonError};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+
+type ReadyCallback = Box<dyn FnOnce() + Send>;
+
+/// Notified once the word list has loaded. Called on the loading thread.
+pub trait ReadyListener: Send + Sync {
+    fn on_ready(&self);
+}
+
+pub struct Resources {
+    pub layout: LayoutDef,
+    /// Physical key positions of the layout's keys
+    pub keymap: Keymap,
+    /// Word list, shared with every engine's `Dictionary`
+    pub lexicon: Arc<LexiconCell>,
+    /// Glide templates for every word of `lexicon`
+    gestures: OnceLock<GestureDecoder>,
+    loading: Once,
+    background_started: AtomicBool,
+    listeners: Mutex<Vec<ReadyCallback>>,
+}
+
+impl Resources {
+    /// Resources with everything loaded
+    pub fn new(layout: LayoutDef, lexicon: Arc<Lexicon>) -> Self {
+        let resources = Self::unloaded(layout);
+        resources.loading.call_once(|| resources.fill(lexicon));
+        resources
+    }
+
+    /// Resources with only the layout; see `load_language`
+    pub fn unloaded(layout: LayoutDef) -> Self {
+        Self {
+            keymap: Keymap::new(&layout),
+            layout,
+            lexicon: Arc::default(),
+            gestures: OnceLock::new(),
+            loading: Once::new(),
+            background_started: AtomicBool::new(false),
+            listeners: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// PM0100 with the base dictionary, fully loaded
+    pub fn pm0100() -> Result<Arc<Self>, IllakiyaError> {
+        let resources = Self::pm0100_layout()?;
+        resources.load_language();
+        Ok(resources)
+    }
+
+    /// PM0100, parsed once per process. The word list may not be loaded
+    /// yet: call `load_language` or `load_in_background`.
+    pub fn pm0100_layout() -> Result<Arc<Self>, IllakiyaError> {
+        static PM0100: OnceLock<Result<Arc<Resources>, IllakiyaError>> = OnceLock::new();
+        PM0100.get_or_init(|| {
+            Ok(Arc::new(Self::unloaded(LayoutDef::load_pm0100()?)))
+        }).clone()
+    }
+
+    /// A custom layout (same format as pm0100.json), not yet loaded
+    pub fn with_layout(layout_json: &str) -> Result<Arc<Self>, IllakiyaError> {
+        Ok(Arc::new(Self::unloaded(LayoutDef::from_json(layout_json)?)))
+    }
+
+    /// True once the word list and glide templates are available
+    pub fn is_ready(&self) -> bool {
+        self.lexicon.is_ready()
+    }
+
+    pub fn gestures(&self) -> Option<&GestureDecoder> {
+        self.gestures.get()
+    }
+
+    /// Load the word list and glide templates, blocking until they are
+    /// ready. Concurrent callers wait for the first; later calls return
+    /// at once.
+    pub fn load_language(&self) {
+        self.loading.call_once(|| self.fill(Self::base_lexicon()));
+        let listeners = std::mem::take(&mut *self.listeners.lock().unwrap_or_else(PoisonError::into_inner));
+        for listener in listeners {
+            listener();
+        }
+    }
+
+    /// `load_language` on a background thread, started at most once
+    pub fn load_in_background(self: &Arc<Self>) {
+        if self.is_ready() || self.background_started.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        // wasm32 has no threads: load on the caller's thread
+        #[cfg(target_arch = "wasm32")]
+        self.load_language();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let resources = Arc::clone(self);
+            thread::spawn(move || resources.load_language());
+        }
+    }
+
+    /// Run `callback` once the word list is ready: now if it already is,
+    /// otherwise on the loading thread
+    pub fn on_ready(&self, callback: impl FnOnce() + Send + 'static) {
+        let mut listeners = self.listeners.lock().unwrap_or_else(PoisonError::into_inner);
+        // Checked under the lock the loader drains, so no callback is lost
+        if self.is_ready() {
+            drop(listeners);
+            callback();
+        } else {
+            listeners.push(Box::new(callback));
+        }
+    }
+
+    /// Templates first: readiness is read from the lexicon
+    fn fill(&self, lexicon: Arc<Lexicon>) {
+        let _ = self.gestures.set(GestureDecoder::new(&self.layout, &lexicon));
+        self.lexicon.set(lexicon);
+    }
+
+    /// The base word list, or an empty one if it fails to load: typing
+    /// works without it, only suggestions and hints go quiet
+    fn base_lexicon() -> Arc<Lexicon> {
+        Lexicon::base().unwrap_or_else(|e| {
+            log::warn!("running without a dictionary: {}", e);
+            Arc::new(Lexicon::empty())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    fn custom() -> Arc<Resources> {
+        Resources::with_layout(include_str!("../../data/layouts/pm0100.json")).unwrap()
+    }
+
+    #[test]
+    fn test_pm0100_is_loaded_once() {
+        let a = Resources::pm0100().unwrap();
+        let b = Resources::pm0100().unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(a.is_ready() && a.gestures().is_some());
+    }
+
+    #[test]
+    fn test_custom_layout_shares_lexicon() {
+        let custom = custom();
+        assert!(!custom.is_ready());
+        custom.load_language();
+        let base = Resources::pm0100().unwrap();
+        assert!(Arc::ptr_eq(custom.lexicon.get().unwrap(), base.lexicon.get().unwrap()));
+    }
+
+    #[test]
+    fn test_background_loading_notifies() {
+        let resources = custom();
+        let (tx, rx) = mpsc::channel();
+        let early = tx.clone();
+        resources.on_ready(move || early.send("early").unwrap());
+        resources.load_in_background();
+        assert_eq!(rx.recv_timeout(Duration::from_secs(10)), Ok("early"));
+        assert!(resources.is_ready());
+        // Registered after loading: runs at once
+        resources.on_ready(move || tx.send("late").unwrap());
+        assert_eq!(rx.try_recv(), Ok("late"));
+    }
+}
@@ -1,4 +1,8 @@
+#[cfg(feature = "onnx")]
+use crate::error::IllakiyaError;
 use crate::tamil;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, PoisonError};
 
 /// Adhan-Sandhi: Tamil word-joining (Punarchi) engine.
 ///
@@ -17,7 +21,34 @@ use crate::tamil;
 
 pub struct AdhanSandhi {
     /// Learning mode: collect user corrections
-    corrections: Vec<SandhiCorrection>,
+    corrections: Mutex<Vec<SandhiCorrection>>,
+    /// When false, corrections are not recorded (incognito)
+    learning_enabled: AtomicBool,
+}
+
+/// Phase 2 model for ambiguous joins (feature `onnx`). Loading checks
+/// the model file up front; scoring with it is not wired in yet.
+#[cfg(feature = "onnx")]
+pub struct SandhiModel {
+    pub session: ort::Session,
+}
+
+#[cfg(feature = "onnx")]
+impl SandhiModel {
+    /// Load the ONNX model at `path`
+    pub fn load(path: &str) -> Result<Self, IllakiyaError> {
+        let model_load = |e: ort::OrtError| IllakiyaError::ModelLoad { message: e.to_string() };
+        let environment = ort::Environment::builder()
+            .with_name("illakiya-sandhi")
+            .build()
+            .map_err(model_load)?
+            .into_arc();
+        let session = ort::SessionBuilder::new(&environment)
+            .map_err(model_load)?
+            .with_model_from_file(path)
+            .map_err(model_load)?;
+        Ok(Self { session })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -49,7 +80,8 @@ pub struct SandhiResult {
 impl AdhanSandhi {
     pub fn new() -> Self {
         Self {
-            corrections: Vec::new(),
+            corrections: Mutex::new(Vec::new()),
+            learning_enabled: AtomicBool::new(true),
         }
     }
 
@@ -152,6 +184,9 @@ impl AdhanSandhi {
         if w1.len() < 2 { return None; }
         let consonant_before_pulli = w1[w1.len() - 2];
         
+        // Grantha finals (ராஜ், க்ஷ்) are loanword endings: no nasal change
+        if tamil::is_grantha(consonant_before_pulli) { return None; }
+
         // If it's a mellinam already, just join
         if tamil::is_mellinam(consonant_before_pulli) {
             return Some(SandhiResult {
@@ -234,9 +269,10 @@ impl AdhanSandhi {
     }
 
     /// Record a user correction for future learning
-    pub fn record_correction(&mut self, word1: &str, word2: &str, expected: &str) {
+    pub fn record_correction(&self, word1: &str, word2: &str, expected: &str) {
+        if !self.learning_enabled.load(Ordering::Relaxed) { return; }
         let analysis = self.analyze(word1, word2);
-        self.corrections.push(SandhiCorrection {
+        self.corrections.lock().unwrap_or_else(PoisonError::into_inner).push(SandhiCorrection {
             word1: word1.to_string(),
             word2: word2.to_string(),
             expected: expected.to_string(),
@@ -244,9 +280,14 @@ impl AdhanSandhi {
         });
     }
 
+    /// Enable or disable correction recording
+    pub fn set_learning_enabled(&self, enabled: bool) {
+        self.learning_enabled.store(enabled, Ordering::Relaxed);
+    }
+
     /// Get corrections log (for training data export)
     pub fn get_corrections_count(&self) -> u32 {
-        self.corrections.len() as u32
+        self.corrections.lock().unwrap_or_else(PoisonError::into_inner).len() as u32
     }
 }
 
@@ -280,6 +321,22 @@ mod tests {
         assert!(r.output.contains("க்"));
     }
 
+    #[test]
+    fn test_no_doubling_before_grantha() {
+        let s = AdhanSandhi::new();
+        let r = s.analyze("பூ", "ஜாடி");
+        assert_eq!(r.rule, SandhiRule::IyalbuPunarchi);
+        assert_eq!(r.output, "பூஜாடி");
+    }
+
+    #[test]
+    fn test_grantha_final_not_nasalised() {
+        let s = AdhanSandhi::new();
+        let r = s.analyze("ராஜ்", "கதை");
+        assert_ne!(r.rule, SandhiRule::MellinamMigu);
+        assert!(r.output.starts_with("ராஜ்"));
+    }
+
     #[test]
     fn test_idaiyinam_insertion() {
         let s = AdhanSandhi::new();
@@ -307,8 +364,16 @@ mod tests {
 
     #[test]
     fn test_correction_recording() {
-        let mut s = AdhanSandhi::new();
+        let s = AdhanSandhi::new();
         s.record_correction("பூ", "கொடி", "பூக்கொடி");
         assert_eq!(s.get_corrections_count(), 1);
     }
+
+    #[test]
+    fn test_learning_disabled_records_no_correction() {
+        let s = AdhanSandhi::new();
+        s.set_learning_enabled(false);
+        s.record_correction("பூ", "கொடி", "பூக்கொடி");
+        assert_eq!(s.get_corrections_count(), 0);
+    }
 }
@@ -0,0 +1,131 @@
+//! Per-input-field typing state.
+//!
+//! A `KeyboardEngine` owns one `Session` per text field the host has
+//! focused (chat box, search bar, ...). Sessions hold everything tied to
+//! the text being edited; the dictionary and sandhi rules are shared.
+
+use crate::context::InputContext;
+use crate::gesture::GestureRecord;
+use crate::history::{History, TextState};
+use crate::layout::Layer;
+use crate::touch::TapRecord;
+
+/// Name of the session every engine starts with
+pub const DEFAULT_SESSION: &str = "default";
+
+#[derive(Default)]
+pub struct Session {
+    /// Committed text before the composing region
+    pub buffer: String,
+    pub pending_consonant: Option<String>,
+    /// Active key layer (nedil, grantha, symbols, ...)
+    pub layer: Layer,
+    /// The layer stays on after a key; otherwise it is a one-shot shift
+    pub layer_locked: bool,
+    /// Word boundaries for sandhi detection
+    pub words: Vec<String>,
+    /// Current word being typed
+    pub current_word: String,
+    /// Rest of the current word after the cursor, when the cursor was
+    /// moved into an existing word
+    pub word_tail: String,
+    /// Undo/redo stack of text edits
+    pub history: History,
+    /// What kind of field this session edits
+    pub context: InputContext,
+    /// Last key decoded from a tap, with its alternatives
+    pub last_tap: Option<TapRecord>,
+    /// Last word decoded from a glide, with the runners-up
+    pub last_gesture: Option<GestureRecord>,
+    /// Text was typed here in incognito or while the field was a password
+    /// field; the session is never snapshotted until it is reset
+    pub private_text: bool,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Capture the text state for the undo history
+    pub fn text_state(&self) -> TextState {
+        TextState {
+            buffer: self.buffer.clone(),
+            words: self.words.clone(),
+            current_word: self.current_word.clone(),
+            pending_consonant: self.pending_consonant.clone(),
+        }
+    }
+
+    /// True if the text state differs from `state`
+    pub fn differs_from(&self, state: &TextState) -> bool {
+        state.buffer != self.buffer
+            || state.current_word != self.current_word
+            || state.pending_consonant != self.pending_consonant
+            || state.words != self.words
+    }
+
+    /// Clear the text state; undo history is kept so "clear" can be undone
+    pub fn clear_text(&mut self) {
+        self.buffer.clear();
+        self.pending_consonant = None;
+        self.set_layer(Layer::Base, false);
+        self.words.clear();
+        self.current_word.clear();
+        self.word_tail.clear();
+        self.last_tap = None;
+        self.last_gesture = None;
+    }
+
+    pub fn set_layer(&mut self, layer: Layer, locked: bool) {
+        self.layer = layer;
+        self.layer_locked = locked && layer != Layer::Base;
+    }
+
+    /// Drop a one-shot shift; locked layers stay on
+    pub fn release_one_shot(&mut self) {
+        if !self.layer_locked {
+            self.layer = Layer::Base;
+        }
+    }
+
+    /// Clear text state and history
+    pub fn reset(&mut self) {
+        self.clear_text();
+        self.history.clear();
+        self.private_text = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_session_is_empty() {
+        let session = Session::new();
+        assert!(!session.differs_from(&TextState::default()));
+        assert!(!session.history.can_undo());
+    }
+
+    #[test]
+    fn test_release_one_shot_keeps_locked_layer() {
+        let mut session = Session::new();
+        session.set_layer(Layer::Nedil, false);
+        session.release_one_shot();
+        assert_eq!(session.layer, Layer::Base);
+        session.set_layer(Layer::Symbols, true);
+        session.release_one_shot();
+        assert_eq!(session.layer, Layer::Symbols);
+    }
+
+    #[test]
+    fn test_clear_text_keeps_history() {
+        let mut session = Session::new();
+        session.current_word.push('த');
+        session.history.record(crate::history::EditOp::Typing, TextState::default());
+        session.clear_text();
+        assert!(session.current_word.is_empty());
+        assert!(session.history.can_undo());
+    }
+}
@@ -0,0 +1,120 @@
+//! Serializable engine state, so hosts can persist the engine across
+//! process death (Android recreates IME services often).
+//!
+//! Snapshots are versioned JSON blobs. Undo history is not included:
+//! it is only meaningful against the exact host text it was built on.
+
+use crate::error::IllakiyaError;
+use crate::layout::Layer;
+use crate::session::Session;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Current snapshot format version. Bump when fields change meaning.
+/// Version 2 keeps every session rather than only the active one.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+/// Text state of one input field
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub buffer: String,
+    pub words: Vec<String>,
+    pub current_word: String,
+    pub pending_consonant: Option<String>,
+    pub layer: Layer,
+    pub layer_locked: bool,
+}
+
+impl SessionSnapshot {
+    pub fn of(session: &Session) -> Self {
+        Self {
+            buffer: session.buffer.clone(),
+            words: session.words.clone(),
+            current_word: session.current_word.clone(),
+            pending_consonant: session.pending_consonant.clone(),
+            layer: session.layer,
+            layer_locked: session.layer_locked,
+        }
+    }
+
+    /// A session holding this state, with empty history
+    pub fn into_session(self) -> Session {
+        let mut session = Session {
+            buffer: self.buffer,
+            words: self.words,
+            current_word: self.current_word,
+            pending_consonant: self.pending_consonant,
+            ..Default::default()
+        };
+        session.set_layer(self.layer, self.layer_locked);
+        session
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub version: u32,
+    /// Sessions by name; incognito and password sessions are left out
+    pub sessions: BTreeMap<String, SessionSnapshot>,
+    /// Name of the session that was active
+    pub active_session: String,
+    /// Dictionary recency list, most recent first
+    pub recents: Vec<String>,
+}
+
+impl EngineSnapshot {
+    /// Serialize to a JSON blob
+    pub fn to_json(&self) -> String {
+        // Plain strings, vectors and bools cannot fail to serialize
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parse a blob produced by `to_json`.
+    /// Fails for malformed blobs or unknown versions.
+    pub fn from_json(blob: &str) -> Result<Self, IllakiyaError> {
+        let snapshot: Self = serde_json::from_str(blob)
+            .map_err(|e| IllakiyaError::UserDataIo { message: e.to_string() })?;
+        if snapshot.version != SNAPSHOT_VERSION {
+            return Err(IllakiyaError::UserDataIo {
+                message: format!("unknown snapshot version {}", snapshot.version),
+            });
+        }
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let field = SessionSnapshot {
+            buffer: "நான் ".to_string(),
+            words: vec!["நான்".to_string()],
+            current_word: "த".to_string(),
+            pending_consonant: Some("ம்".to_string()),
+            layer: Layer::Nedil,
+            layer_locked: false,
+        };
+        let snapshot = EngineSnapshot {
+            version: SNAPSHOT_VERSION,
+            sessions: BTreeMap::from([("chat".to_string(), field)]),
+            active_session: "chat".to_string(),
+            recents: vec!["நான்".to_string()],
+        };
+        let blob = snapshot.to_json();
+        assert_eq!(EngineSnapshot::from_json(&blob), Ok(snapshot));
+    }
+
+    #[test]
+    fn test_rejects_unknown_version() {
+        let snapshot = EngineSnapshot { version: SNAPSHOT_VERSION + 1, ..Default::default() };
+        assert!(EngineSnapshot::from_json(&snapshot.to_json()).is_err());
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        assert!(matches!(EngineSnapshot::from_json("not json"), Err(IllakiyaError::UserDataIo { .. })));
+    }
+}
@@ -13,6 +13,9 @@ const MELLINAM: [char; 6] = ['ங', 'ஞ', 'ண', 'ந', 'ம', 'ன'];
 /// Idaiyinam (Medium consonants): ய, ர, ல, வ, ழ, ள
 const IDAIYINAM: [char; 6] = ['ய', 'ர', 'ல', 'வ', 'ழ', 'ள'];
 
+/// Grantha (Sanskrit loan) consonants: ஜ, ஷ, ஸ, ஹ (க்ஷ is க்+ஷ)
+const GRANTHA: [char; 4] = ['ஜ', 'ஷ', 'ஸ', 'ஹ'];
+
 /// Short vowels: அ, இ, உ, எ, ஒ
 const KURIL: [char; 5] = ['அ', 'இ', 'உ', 'எ', 'ஒ'];
 
@@ -39,11 +42,14 @@ pub fn vallinam_to_mellinam(c: char) -> Option<char> {
 pub fn is_vallinam(c: char) -> bool { VALLINAM.contains(&c) }
 pub fn is_mellinam(c: char) -> bool { MELLINAM.contains(&c) }
 pub fn is_idaiyinam(c: char) -> bool { IDAIYINAM.contains(&c) }
+pub fn is_grantha(c: char) -> bool { GRANTHA.contains(&c) }
 pub fn is_kuril(c: char) -> bool { KURIL.contains(&c) }
 pub fn is_nedil(c: char) -> bool { NEDIL.contains(&c) }
 pub fn is_pulli(c: char) -> bool { c == PULLI }
 pub fn is_uyir(c: char) -> bool { is_kuril(c) || is_nedil(c) }
-pub fn is_mei(c: char) -> bool { is_vallinam(c) || is_mellinam(c) || is_idaiyinam(c) }
+pub fn is_mei(c: char) -> bool {
+    is_vallinam(c) || is_mellinam(c) || is_idaiyinam(c) || is_grantha(c)
+}
 
 /// Get the base consonant from an uyirmei character.
 /// Tamil Unicode: uyirmei range 0x0B95-0x0BD7
@@ -57,6 +63,33 @@ pub fn get_base_consonant(c: char) -> Option<char> {
     None
 }
 
+/// Mei mayakkam: can consonant `next` follow the mei `mei` inside a
+/// word? Follows Tholkaappiyam's cluster rules (க்க, ங்க, ண்ட, ய்ப, ...).
+/// Grantha letters are exempt.
+pub fn can_follow(mei: char, next: char) -> bool {
+    if is_grantha(mei) || is_grantha(next) {
+        return true;
+    }
+    let allowed: &[char] = match mei {
+        'க' | 'ச' | 'த' | 'ப' => return next == mei,
+        'ட' => &['க', 'ச', 'ட', 'ப'],
+        'ற' => &['க', 'ச', 'ப', 'ற'],
+        'ங' => &['க', 'ங'],
+        'ஞ' => &['ச', 'ஞ', 'ய'],
+        'ந' => &['த', 'ந', 'ய'],
+        'ம' => &['ப', 'ம', 'ய', 'வ'],
+        'ண' => &['ட', 'க', 'ச', 'ஞ', 'ப', 'ம', 'ய', 'வ', 'ண'],
+        'ன' => &['ற', 'க', 'ச', 'ஞ', 'ப', 'ம', 'ய', 'வ', 'ன'],
+        'ய' => &['க', 'ச', 'த', 'ப', 'ங', 'ஞ', 'ந', 'ம', 'வ', 'ய'],
+        'ர' | 'ழ' => &['க', 'ச', 'த', 'ப', 'ங', 'ஞ', 'ந', 'ம', 'வ', 'ய'],
+        'ல' => &['க', 'ச', 'ப', 'வ', 'ய', 'ல'],
+        'ள' => &['க', 'ச', 'ப', 'வ', 'ய', 'ள'],
+        'வ' => &['ய', 'வ'],
+        _ => return false,
+    };
+    allowed.contains(&next)
+}
+
 /// Check if the last character of a string ends in a short vowel sound
 /// (either a standalone kuril or an uyirmei with inherent 'a')
 pub fn ends_with_short_vowel(word: &str) -> bool {
@@ -84,6 +117,74 @@ pub fn ends_with_pulli(word: &str) -> bool {
     word.chars().last().map_or(false, is_pulli)
 }
 
+/// Dependent vowel sign (matra) that attaches a vowel to a consonant.
+/// அ is inherent and has no sign; returns None for it and for non-vowels.
+pub fn vowel_sign(vowel: char) -> Option<char> {
+    match vowel {
+        'ஆ' => Some('\u{0BBE}'),
+        'இ' => Some('\u{0BBF}'),
+        'ஈ' => Some('\u{0BC0}'),
+        'உ' => Some('\u{0BC1}'),
+        'ஊ' => Some('\u{0BC2}'),
+        'எ' => Some('\u{0BC6}'),
+        'ஏ' => Some('\u{0BC7}'),
+        'ஐ' => Some('\u{0BC8}'),
+        'ஒ' => Some('\u{0BCA}'),
+        'ஓ' => Some('\u{0BCB}'),
+        'ஔ' => Some('\u{0BCC}'),
+        _ => None,
+    }
+}
+
+/// Vowel carried by a dependent vowel sign: ா -> ஆ
+pub fn vowel_for_sign(sign: char) -> Option<char> {
+    KURIL.iter().chain(NEDIL.iter())
+        .copied()
+        .find(|&v| vowel_sign(v) == Some(sign))
+}
+
+/// Dependent vowel signs and pulli, which attach to the preceding consonant
+pub fn is_dependent_sign(c: char) -> bool {
+    ('\u{0BBE}'..='\u{0BCD}').contains(&c) || c == '\u{0BD7}'
+}
+
+/// Compose a mei (consonant + pulli, e.g. "க்" or "க்ஷ்") with an uyir
+/// into uyirmei, appending the result to `out`: "க்" + "ஆ" -> "கா".
+/// Returns false, leaving `out` untouched, if the pair does not compose.
+pub fn compose_into(mei: &str, vowel: &str, out: &mut String) -> bool {
+    let Some(stem) = mei.strip_suffix(PULLI) else { return false };
+    let mut vowel_chars = vowel.chars();
+    let (Some(v), None) = (vowel_chars.next(), vowel_chars.next()) else { return false };
+    if !is_uyir(v) || !stem.chars().last().is_some_and(is_mei) {
+        return false;
+    }
+    out.push_str(stem);
+    if let Some(sign) = vowel_sign(v) {
+        out.push(sign);
+    }
+    true
+}
+
+/// Allocating form of `compose_into`
+pub fn compose(mei: &str, vowel: &str) -> Option<String> {
+    let mut out = String::new();
+    compose_into(mei, vowel, &mut out).then_some(out)
+}
+
+/// Tamil digit zero (௦); digits ௦-௯ are contiguous from here
+const TAMIL_DIGIT_ZERO: u32 = 0x0BE6;
+
+/// Render ASCII digits as Tamil digits (positional), leaving other
+/// characters untouched: "2024" -> "௨௦௨௪"
+pub fn to_tamil_digits(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '0'..='9' => char::from_u32(TAMIL_DIGIT_ZERO + (c as u32 - '0' as u32)).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
 /// Get the first consonant of a word (stripping vowel signs)
 pub fn first_consonant(word: &str) -> Option<char> {
     word.chars().next().filter(|&c| is_mei(c) || is_vallinam(c))
@@ -0,0 +1,145 @@
+//! Touch-model key disambiguation.
+//!
+//! A tap is scored against every key of the layout geometry with a
+//! Gaussian touch model, then weighted by the dictionary's next-key
+//! probabilities. The best key is typed; the ranked alternatives are
+//! kept so the host can offer a correction.
+
+use crate::history::TextState;
+use crate::layout::{Layer, LayoutDef};
+use std::collections::HashMap;
+
+/// Spread of taps around a key centre, in key widths
+const TOUCH_SIGMA: f32 = 0.5;
+
+/// Keys further than this from the tap (in key widths) are not candidates
+const TOUCH_RADIUS: f32 = 1.5;
+
+/// Keeps keys the dictionary rules out typeable: a key with dictionary
+/// probability 0 still gets this share of an average key's weight
+const DICT_SMOOTHING: f32 = 0.5;
+
+/// A key and how likely it was meant
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyCandidate {
+    pub key: String,
+    pub probability: f32,
+}
+
+/// The last decoded tap, kept so it can be corrected
+#[derive(Debug, Clone)]
+pub struct TapRecord {
+    /// Text state before the tap was typed
+    pub before: TextState,
+    pub layer: Layer,
+    pub layer_locked: bool,
+    /// Text state right after; the record is stale once this changes
+    pub after: TextState,
+    /// Ranked candidates, the typed key first
+    pub alternatives: Vec<KeyCandidate>,
+}
+
+/// Touch likelihood of each key near a tap at (`x`, `y`), both
+/// normalised to 0..1 of the keyboard's width and height. Rows are
+/// stretched to the full width, as front-ends render them.
+pub fn touch_distribution(layout: &LayoutDef, x: f32, y: f32) -> Vec<KeyCandidate> {
+    let rows = &layout.geometry.rows;
+    let mut candidates = Vec::new();
+    for (r, row) in rows.iter().enumerate() {
+        let row_width: f32 = row.iter().map(|k| k.width).sum();
+        if row_width <= 0.0 {
+            continue;
+        }
+        // Distances in key units: one key width across, one row down
+        let dy = y * rows.len() as f32 - (r as f32 + 0.5);
+        let mut left = 0.0;
+        for key in row {
+            let dx = x * row_width - (left + key.width / 2.0);
+            left += key.width;
+            let dist_sq = dx * dx + dy * dy;
+            if dist_sq > TOUCH_RADIUS * TOUCH_RADIUS {
+                continue;
+            }
+            candidates.push(KeyCandidate {
+                key: key.key.clone(),
+                probability: (-dist_sq / (2.0 * TOUCH_SIGMA * TOUCH_SIGMA)).exp(),
+            });
+        }
+    }
+    normalise(&mut candidates);
+    candidates
+}
+
+/// Combine a touch distribution with dictionary next-key probabilities
+/// (`hints::next_key_probabilities`). Returns candidates ranked best
+/// first. Keys without a dictionary score keep their touch likelihood.
+pub fn decode(touch: &[KeyCandidate], dict: &HashMap<String, f32>) -> Vec<KeyCandidate> {
+    let letters = dict.len() as f32;
+    let mut ranked: Vec<KeyCandidate> = touch.iter()
+        .map(|c| {
+            // Scaled so an average letter key keeps its touch likelihood
+            let prior = dict.get(&c.key)
+                .map_or(1.0, |p| (DICT_SMOOTHING + p * letters) / (DICT_SMOOTHING + 1.0));
+            KeyCandidate { key: c.key.clone(), probability: c.probability * prior }
+        })
+        .collect();
+    normalise(&mut ranked);
+    ranked.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+    ranked
+}
+
+fn normalise(candidates: &mut [KeyCandidate]) {
+    let total: f32 = candidates.iter().map(|c| c.probability).sum();
+    if total > 0.0 {
+        candidates.iter_mut().for_each(|c| c.probability /= total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn centre(layout: &LayoutDef, key: &str) -> (f32, f32) {
+        layout.key_centres()[key]
+    }
+
+    #[test]
+    fn test_tap_on_centre_picks_key() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let (x, y) = centre(&layout, "w");
+        let touch = touch_distribution(&layout, x, y);
+        let best = decode(&touch, &HashMap::new());
+        assert_eq!(best[0].key, "w");
+        assert!(best.iter().any(|c| c.key == "q"));
+        assert!(!best.iter().any(|c| c.key == "space"));
+    }
+
+    #[test]
+    fn test_distribution_is_normalised() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let touch = touch_distribution(&layout, 0.5, 0.5);
+        let sum: f32 = touch.iter().map(|c| c.probability).sum();
+        assert!((sum - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_dictionary_breaks_tie() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let (xq, y) = centre(&layout, "q");
+        let (xw, _) = centre(&layout, "w");
+        let touch = touch_distribution(&layout, (xq + xw) / 2.0, y);
+        let dict = HashMap::from([("q".to_string(), 0.0), ("w".to_string(), 1.0)]);
+        assert_eq!(decode(&touch, &dict)[0].key, "w");
+        let dict = HashMap::from([("q".to_string(), 1.0), ("w".to_string(), 0.0)]);
+        assert_eq!(decode(&touch, &dict)[0].key, "q");
+    }
+
+    #[test]
+    fn test_touch_outweighs_weak_prior() {
+        let layout = LayoutDef::load_pm0100().unwrap();
+        let (x, y) = centre(&layout, "q");
+        let touch = touch_distribution(&layout, x, y);
+        let dict = HashMap::from([("q".to_string(), 0.3), ("w".to_string(), 0.7)]);
+        assert_eq!(decode(&touch, &dict)[0].key, "q");
+    }
+}
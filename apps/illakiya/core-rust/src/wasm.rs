@@ -0,0 +1,358 @@
+//! WebAssembly bindings for the web prototype (wasm-bindgen).
+//!
+//! The typing school and notepad in `web/` drive the same engine as the
+//! Android IME instead of a TypeScript copy of the PM0100 rules. Build
+//! with `npm run wasm` in `web/` (`wasm-pack build --target web`); the
+//! layout and word list are embedded, and `ort` is never linked. Tests
+//! run headless with `wasm-pack test --node`.
+//!
+//! wasm32 has no threads, so nothing loads in the background: the engine
+//! types as soon as it is constructed, and the page calls
+//! `loadDictionary()` once it has painted to get suggestions and glides.
+//! Events are separate methods (`keyTap`, `flick`, `backspace`, ...)
+//! rather than a tagged object, and enums cross as their names ("nedil",
+//! "up_left").
+
+use crate::dictionary::Dictionary;
+use crate::edit::EditResult;
+use crate::event::{BackspaceMode, InputEvent};
+use crate::gesture::TouchPoint;
+use crate::handle::KeyboardEngine;
+use crate::layout::{FlickDirection, Layer};
+use crate::resources::Resources;
+use crate::sandhi::AdhanSandhi;
+use std::sync::Arc;
+use wasm_bindgen::prelude::*;
+
+fn layer_named(name: &str) -> Result<Layer, JsError> {
+    Layer::ALL.into_iter()
+        .find(|layer| layer.name() == name)
+        .ok_or_else(|| JsError::new(&format!("unknown layer: {}", name)))
+}
+
+/// An `EditResult`; apply it in the order documented there
+#[wasm_bindgen(getter_with_clone)]
+pub struct Edit {
+    #[wasm_bindgen(js_name = deleteBefore)]
+    pub delete_before: u32,
+    #[wasm_bindgen(js_name = deleteAfter)]
+    pub delete_after: u32,
+    pub commit: String,
+    pub composing: String,
+    #[wasm_bindgen(js_name = nedilActive)]
+    pub nedil_active: bool,
+    pub pending: bool,
+    /// Layer name ("base", "nedil", ...)
+    pub layer: String,
+}
+
+impl From<EditResult> for Edit {
+    fn from(edit: EditResult) -> Self {
+        Self {
+            delete_before: edit.delete_before,
+            delete_after: edit.delete_after,
+            commit: edit.commit,
+            composing: edit.composing,
+            nedil_active: edit.nedil_active,
+            pending: edit.pending,
+            layer: edit.layer.name().to_string(),
+        }
+    }
+}
+
+/// One key of the keyboard model, as the page should draw it
+#[wasm_bindgen(getter_with_clone)]
+pub struct Key {
+    /// Row index, top first
+    pub row: u32,
+    /// Key id to pass to `keyTap`
+    pub key: String,
+    pub primary: String,
+    pub secondary: Option<String>,
+    /// Width in key units
+    pub width: f32,
+    /// False when the key cannot follow the pending consonant
+    pub valid: bool,
+}
+
+#[wasm_bindgen(js_name = KeyboardEngine)]
+pub struct WasmKeyboardEngine {
+    engine: KeyboardEngine,
+    resources: Arc<Resources>,
+}
+
+#[wasm_bindgen(js_class = KeyboardEngine)]
+impl WasmKeyboardEngine {
+    /// PM0100, without the word list until `loadDictionary()`
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmKeyboardEngine, JsError> {
+        Ok(Self::with_resources(Resources::pm0100_layout()?))
+    }
+
+    /// A custom layout (same format as pm0100.json)
+    #[wasm_bindgen(js_name = withLayout)]
+    pub fn with_layout(layout_json: &str) -> Result<WasmKeyboardEngine, JsError> {
+        Ok(Self::with_resources(Resources::with_layout(layout_json)?))
+    }
+
+    fn with_resources(resources: Arc<Resources>) -> Self {
+        Self { engine: KeyboardEngine::with_resources(resources.clone()), resources }
+    }
+
+    /// Build the word list and glide templates. Blocks the page for the
+    /// length of a cold start; later calls return at once.
+    #[wasm_bindgen(js_name = loadDictionary)]
+    pub fn load_dictionary(&self) {
+        self.resources.load_language();
+    }
+
+    #[wasm_bindgen(js_name = isDictionaryReady)]
+    pub fn is_dictionary_ready(&self) -> bool {
+        self.engine.is_dictionary_ready()
+    }
+
+    #[wasm_bindgen(js_name = keyTap)]
+    pub fn key_tap(&self, key: &str) -> Edit {
+        self.engine.process_event(InputEvent::KeyTap { key: key.to_string() }).into()
+    }
+
+    /// A tap at normalised keyboard coordinates, decoded to the likeliest key
+    pub fn tap(&self, x: f32, y: f32) -> Edit {
+        self.engine.process_tap(x, y).into()
+    }
+
+    /// `direction`: "up", "up_right", "right", ... "up_left"
+    pub fn flick(&self, key: &str, direction: &str) -> Result<Edit, JsError> {
+        let direction: FlickDirection = serde_json::from_value(direction.into())
+            .map_err(|_| JsError::new(&format!("unknown flick direction: {}", direction)))?;
+        Ok(self.engine.process_event(InputEvent::Flick { key: key.to_string(), direction }).into())
+    }
+
+    /// A glide as flat `[x0, y0, x1, y1, ...]`, normalised to the keyboard
+    pub fn swipe(&self, points: &[f32]) -> Edit {
+        let points = points.chunks_exact(2).map(|p| TouchPoint { x: p[0], y: p[1] }).collect();
+        self.engine.process_event(InputEvent::Swipe { points }).into()
+    }
+
+    /// One character, or the whole word when `word` is set
+    pub fn backspace(&self, word: bool) -> Edit {
+        let mode = if word { BackspaceMode::Word } else { BackspaceMode::Char };
+        self.engine.process_event(InputEvent::Backspace { mode }).into()
+    }
+
+    pub fn space(&self) -> Edit {
+        self.engine.process_event(InputEvent::Space).into()
+    }
+
+    pub fn enter(&self) -> Edit {
+        self.engine.process_event(InputEvent::Enter).into()
+    }
+
+    #[wasm_bindgen(js_name = commitText)]
+    pub fn commit_text(&self, text: &str) -> Edit {
+        self.engine.process_event(InputEvent::CommitText { text: text.to_string() }).into()
+    }
+
+    #[wasm_bindgen(js_name = setLayer)]
+    pub fn set_layer(&self, layer: &str, locked: bool) -> Result<Edit, JsError> {
+        let layer = layer_named(layer)?;
+        Ok(self.engine.process_event(InputEvent::SetLayer { layer, locked }).into())
+    }
+
+    pub fn clear(&self) -> Edit {
+        self.engine.process_event(InputEvent::Clear).into()
+    }
+
+    #[wasm_bindgen(js_name = finishComposing)]
+    pub fn finish_composing(&self) -> Edit {
+        self.engine.finish_composing().into()
+    }
+
+    #[wasm_bindgen(js_name = acceptSuggestion)]
+    pub fn accept_suggestion(&self, suggestion: &str) -> Edit {
+        self.engine.accept_suggestion(suggestion).into()
+    }
+
+    #[wasm_bindgen(js_name = applySandhi)]
+    pub fn apply_sandhi(&self) -> Edit {
+        self.engine.apply_sandhi().into()
+    }
+
+    /// Re-enter existing text around the caret (after a click or a note switch)
+    #[wasm_bindgen(js_name = setSurroundingText)]
+    pub fn set_surrounding_text(&self, before: &str, after: &str) -> Edit {
+        self.engine.set_surrounding_text(before, after).into()
+    }
+
+    pub fn undo(&self) -> Edit {
+        self.engine.undo().into()
+    }
+
+    pub fn redo(&self) -> Edit {
+        self.engine.redo().into()
+    }
+
+    pub fn suggestions(&self, limit: u32) -> Vec<String> {
+        self.engine.get_suggestions(limit)
+    }
+
+    #[wasm_bindgen(js_name = sandhiSuggestion)]
+    pub fn sandhi_suggestion(&self) -> Option<String> {
+        self.engine.get_sandhi_suggestion()
+    }
+
+    #[wasm_bindgen(js_name = gestureAlternatives)]
+    pub fn gesture_alternatives(&self) -> Vec<String> {
+        self.engine.get_gesture_alternatives()
+    }
+
+    pub fn composing(&self) -> String {
+        self.engine.get_composing()
+    }
+
+    /// Active layer name
+    pub fn layer(&self) -> String {
+        self.engine.get_layer().name().to_string()
+    }
+
+    /// The keys of the active layer, row by row
+    pub fn keys(&self) -> Vec<Key> {
+        let model = self.engine.get_keyboard_model();
+        model.rows.into_iter().enumerate().flat_map(|(row, keys)| {
+            keys.into_iter().map(move |k| Key {
+                row: row as u32,
+                key: k.key,
+                primary: k.primary,
+                secondary: k.secondary,
+                width: k.width,
+                valid: k.valid,
+            })
+        }).collect()
+    }
+
+    pub fn snapshot(&self) -> String {
+        self.engine.snapshot()
+    }
+
+    pub fn restore(&self, blob: &str) -> Result<(), JsError> {
+        Ok(self.engine.restore(blob)?)
+    }
+
+    pub fn reset(&self) {
+        self.engine.reset()
+    }
+}
+
+#[wasm_bindgen(js_name = Dictionary)]
+pub struct WasmDictionary(Dictionary);
+
+#[wasm_bindgen(js_class = Dictionary)]
+impl WasmDictionary {
+    /// The embedded base dictionary
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmDictionary, JsError> {
+        Ok(Self(Dictionary::new()?))
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.0.contains(word)
+    }
+
+    pub fn suggest(&self, prefix: &str, limit: u32) -> Vec<String> {
+        self.0.suggest(prefix, limit as usize)
+    }
+
+    pub fn translate(&self, word: &str) -> Option<String> {
+        self.0.translate(word)
+    }
+
+    pub fn transliterate(&self, word: &str) -> Option<String> {
+        self.0.transliterate(word)
+    }
+
+    #[wasm_bindgen(js_name = wordCount)]
+    pub fn word_count(&self) -> u32 {
+        self.0.word_count()
+    }
+}
+
+#[wasm_bindgen(js_name = AdhanSandhi)]
+pub struct WasmAdhanSandhi(AdhanSandhi);
+
+#[wasm_bindgen(js_class = AdhanSandhi)]
+impl WasmAdhanSandhi {
+    // JS constructs with `new`; a Default impl would not be exported
+    #[allow(clippy::new_without_default)]
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmAdhanSandhi {
+        Self(AdhanSandhi::new())
+    }
+
+    /// The two words joined by the punarchi rules
+    #[wasm_bindgen(js_name = checkPunarchi)]
+    pub fn check_punarchi(&self, word1: &str, word2: &str) -> String {
+        self.0.check_punarchi(word1, word2)
+    }
+
+    #[wasm_bindgen(js_name = recordCorrection)]
+    pub fn record_correction(&self, word1: &str, word2: &str, expected: &str) {
+        self.0.record_correction(word1, word2, expected)
+    }
+
+    #[wasm_bindgen(js_name = correctionsCount)]
+    pub fn corrections_count(&self) -> u32 {
+        self.0.get_corrections_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn type_keys(engine: &WasmKeyboardEngine, keys: &[&str]) -> Edit {
+        let mut last = engine.space();
+        for key in keys {
+            last = engine.key_tap(key);
+        }
+        last
+    }
+
+    #[wasm_bindgen_test]
+    fn test_types_like_android() {
+        let engine = WasmKeyboardEngine::new().unwrap();
+        let edit = type_keys(&engine, &["u", "z", "p", "x", "g"]);
+        assert_eq!(edit.composing, "தமிழ்");
+        assert_eq!(edit.layer, "base");
+        let edit = engine.backspace(true);
+        assert_eq!(edit.composing, "");
+    }
+
+    #[wasm_bindgen_test]
+    fn test_suggestions_after_loading() {
+        let engine = WasmKeyboardEngine::new().unwrap();
+        engine.load_dictionary();
+        assert!(engine.is_dictionary_ready());
+        type_keys(&engine, &["u", "z", "p", "x"]);
+        assert!(engine.suggestions(5).iter().any(|s| s == "தமிழ்"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_flick_and_layers_by_name() {
+        let engine = WasmKeyboardEngine::new().unwrap();
+        // Up is அ: a bare uyirmei
+        assert_eq!(engine.flick("q", "up").ok().unwrap().composing, "க");
+        assert_eq!(engine.set_layer("symbols", true).ok().unwrap().layer, "symbols");
+        assert_eq!(engine.layer(), "symbols");
+        assert!(engine.keys().iter().any(|k| k.primary == "௧"));
+    }
+
+    #[wasm_bindgen_test]
+    fn test_dictionary_and_sandhi() {
+        let dict = WasmDictionary::new().unwrap();
+        assert!(dict.contains("தமிழ்"));
+        assert!(dict.word_count() > 0);
+        let sandhi = WasmAdhanSandhi::new();
+        assert_eq!(sandhi.check_punarchi("பூ", "கடை"), "பூக்கடை");
+    }
+}
@@ -0,0 +1,377 @@
+#include "engine.h"
+
+#include <fcitx-utils/log.h>
+#include <fcitx-utils/utf8.h>
+#include <fcitx/candidatelist.h>
+#include <fcitx/inputcontext.h>
+#include <fcitx/inputcontextmanager.h>
+#include <fcitx/inputpanel.h>
+#include <fcitx/text.h>
+
+#include <algorithm>
+#include <cstring>
+#include <memory>
+
+namespace illakiya {
+
+namespace {
+
+// Suggestions shown after the sandhi join, as on Android
+constexpr uint32_t kSuggestions = 5;
+// evdev code of Backspace
+constexpr uint32_t kBackspaceCode = 14;
+// XKB keycodes are evdev codes offset by 8
+constexpr uint32_t kXkbOffset = 8;
+
+// A lookup table entry; selecting it accepts candidate `index`
+class Candidate : public fcitx::CandidateWord {
+public:
+    Candidate(State *state, size_t index, const std::string &text)
+        : fcitx::CandidateWord(fcitx::Text(text)), state_(state),
+          index_(index) {}
+
+    void select(fcitx::InputContext *) const override {
+        state_->select(index_);
+    }
+
+private:
+    State *state_;
+    size_t index_;
+};
+
+// What the dictionary-ready callback needs to find its state again
+struct ReadyCall {
+    fcitx::EventDispatcher *dispatcher;
+    std::weak_ptr<State *> state;
+};
+
+// Runs on the loading thread: hop to the main loop, where the state may
+// have gone in the meantime
+void onDictionaryReady(void *data) {
+    std::unique_ptr<ReadyCall> call(static_cast<ReadyCall *>(data));
+    call->dispatcher->schedule([state = call->state] {
+        if (auto alive = state.lock()) {
+            (*alive)->dictionaryReady();
+        }
+    });
+}
+
+// Map the client's field description onto `ILLAKIYA_INPUT_*`
+uint32_t inputKind(const fcitx::CapabilityFlags &flags) {
+    if (flags.test(fcitx::CapabilityFlag::Password)) {
+        return ILLAKIYA_INPUT_PASSWORD;
+    }
+    if (flags.test(fcitx::CapabilityFlag::Url)) {
+        return ILLAKIYA_INPUT_URL;
+    }
+    if (flags.test(fcitx::CapabilityFlag::Email)) {
+        return ILLAKIYA_INPUT_EMAIL;
+    }
+    if (flags.testAny(fcitx::CapabilityFlags{fcitx::CapabilityFlag::Digit,
+                                             fcitx::CapabilityFlag::Number,
+                                             fcitx::CapabilityFlag::Dialable})) {
+        return ILLAKIYA_INPUT_NUMBER;
+    }
+    return ILLAKIYA_INPUT_TEXT;
+}
+
+} // namespace
+
+State::State(fcitx::InputContext *ic, fcitx::EventDispatcher *dispatcher)
+    : ic_(ic), self_(std::make_shared<State *>(this)) {
+    IllakiyaStatus status = ILLAKIYA_STATUS_OK;
+    // The layout is parsed once per process; the dictionary loads in the
+    // background and is shared by every input context
+    engine_ = illakiya_engine_new(&status);
+    if (!engine_) {
+        FCITX_ERROR() << "Illakiya engine failed to load: status "
+                      << static_cast<int>(status);
+        return;
+    }
+    auto *call = new ReadyCall{dispatcher, self_};
+    if (illakiya_engine_set_ready_callback(engine_, onDictionaryReady, call) !=
+        ILLAKIYA_STATUS_OK) {
+        delete call;
+    }
+}
+
+State::~State() { illakiya_engine_free(engine_); }
+
+bool State::canDelete() const {
+    return ic_->capabilityFlags().test(fcitx::CapabilityFlag::SurroundingText);
+}
+
+bool State::isComposing() const {
+    char *composing = illakiya_engine_get_composing(engine_);
+    bool result = composing && *composing;
+    illakiya_string_free(composing);
+    return result;
+}
+
+void State::keyEvent(fcitx::KeyEvent &event) {
+    if (!engine_ || event.isRelease()) {
+        return;
+    }
+    if (resync_) {
+        resync();
+    }
+    const fcitx::Key &key = event.key();
+    // Shortcuts act on committed text
+    if (key.states().testAny(fcitx::KeyStates{fcitx::KeyState::Ctrl,
+                                              fcitx::KeyState::Alt,
+                                              fcitx::KeyState::Super})) {
+        commitComposing();
+        return;
+    }
+    auto candidates = ic_->inputPanel().candidateList();
+    if (candidates && candidates->size() > 0) {
+        if (key.sym() == FcitxKey_Tab) {
+            select(std::max(candidates->cursorIndex(), 0));
+            event.filterAndAccept();
+            return;
+        }
+        auto *movable = candidates->toCursorMovable();
+        if (movable && (key.sym() == FcitxKey_Up || key.sym() == FcitxKey_Down)) {
+            if (key.sym() == FcitxKey_Down) {
+                movable->nextCandidate();
+            } else {
+                movable->prevCandidate();
+            }
+            ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+            event.filterAndAccept();
+            return;
+        }
+    }
+    // Enter stays a real key press: single-line fields submit on it
+    if (key.sym() == FcitxKey_Return || key.sym() == FcitxKey_KP_Enter) {
+        commitComposing();
+        return;
+    }
+
+    // By position, so the OS keyboard layout does not matter. Synthetic
+    // events may carry no keycode at all
+    if (event.rawKey().code() < kXkbOffset) {
+        commitComposing();
+        return;
+    }
+    uint32_t code = event.rawKey().code() - kXkbOffset;
+    bool shift = event.rawKey().states().test(fcitx::KeyState::Shift);
+    bool wasComposing = isComposing();
+    bool handled = false;
+    IllakiyaEdit edit;
+    if (illakiya_engine_process_key_code(engine_, code, shift, &handled,
+                                         &edit) != ILLAKIYA_STATUS_OK) {
+        return;
+    }
+    if (!handled) {
+        illakiya_edit_free(&edit);
+        if (!key.isModifier()) {
+            commitComposing();
+        }
+        return;
+    }
+    // Deleting one committed character is what the client's own
+    // Backspace does, and works without surrounding text
+    bool plainBackspace = code == kBackspaceCode && !wasComposing &&
+                          edit.delete_before <= 1 && edit.delete_after == 0 &&
+                          !*edit.commit && !*edit.composing;
+    if (plainBackspace) {
+        illakiya_edit_free(&edit);
+        updateCandidates();
+        ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+        return;
+    }
+    // A longer delete (reverting an autocorrection) would be ignored and
+    // leave both texts: let the client delete one character and start the
+    // engine over from there
+    if (code == kBackspaceCode && !wasComposing && !canDelete()) {
+        illakiya_edit_free(&edit);
+        illakiya_engine_reset(engine_);
+        updateCandidates();
+        ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+        return;
+    }
+    apply(edit);
+    event.filterAndAccept();
+}
+
+void State::apply(IllakiyaEdit &edit) {
+    auto &panel = ic_->inputPanel();
+    bool clientPreedit =
+        ic_->capabilityFlags().test(fcitx::CapabilityFlag::Preedit);
+    uint32_t deletes = edit.delete_before + edit.delete_after;
+    if (deletes > 0 || *edit.commit) {
+        panel.setClientPreedit(fcitx::Text());
+        panel.setPreedit(fcitx::Text());
+        ic_->updatePreedit();
+    }
+    if (deletes > 0) {
+        ic_->deleteSurroundingText(-static_cast<int>(edit.delete_before),
+                                   deletes);
+    }
+    if (*edit.commit) {
+        ic_->commitString(edit.commit);
+    }
+    fcitx::Text preedit;
+    if (*edit.composing) {
+        preedit.append(edit.composing, fcitx::TextFormatFlag::Underline);
+        // Byte offset, at the end of the word
+        preedit.setCursor(static_cast<int>(std::strlen(edit.composing)));
+    }
+    if (clientPreedit) {
+        panel.setClientPreedit(preedit);
+    } else {
+        panel.setPreedit(preedit);
+    }
+    illakiya_edit_free(&edit);
+    updateCandidates();
+    ic_->updatePreedit();
+    ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+}
+
+void State::updateCandidates() {
+    candidates_.clear();
+    // The join replaces the committed word before it, which takes
+    // deleting surrounding text
+    char *join =
+        canDelete() ? illakiya_engine_get_sandhi_suggestion(engine_) : nullptr;
+    sandhi_ = join != nullptr;
+    if (join) {
+        candidates_.emplace_back(join);
+        illakiya_string_free(join);
+    }
+    IllakiyaStringList words;
+    if (illakiya_engine_get_suggestions(engine_, kSuggestions, &words) ==
+        ILLAKIYA_STATUS_OK) {
+        for (size_t i = 0; i < words.len; i++) {
+            candidates_.emplace_back(words.items[i]);
+        }
+        illakiya_string_list_free(&words);
+    }
+
+    auto &panel = ic_->inputPanel();
+    if (candidates_.empty()) {
+        panel.setCandidateList(nullptr);
+        return;
+    }
+    auto list = std::make_unique<fcitx::CommonCandidateList>();
+    list->setLayoutHint(fcitx::CandidateLayoutHint::Horizontal);
+    list->setPageSize(static_cast<int>(candidates_.size()));
+    for (size_t i = 0; i < candidates_.size(); i++) {
+        std::string text = i == 0 && sandhi_ ? "⚡ " + candidates_[i]
+                                             : candidates_[i];
+        list->append<Candidate>(this, i, text);
+    }
+    list->setGlobalCursorIndex(0);
+    panel.setCandidateList(std::move(list));
+}
+
+void State::select(size_t index) {
+    if (index >= candidates_.size()) {
+        return;
+    }
+    IllakiyaEdit edit;
+    IllakiyaStatus status =
+        index == 0 && sandhi_
+            ? illakiya_engine_apply_sandhi(engine_, &edit)
+            : illakiya_engine_accept_suggestion(
+                  engine_, candidates_[index].c_str(), &edit);
+    if (status == ILLAKIYA_STATUS_OK) {
+        apply(edit);
+    }
+}
+
+void State::commitComposing() {
+    if (!engine_ || !isComposing()) {
+        return;
+    }
+    IllakiyaEdit edit;
+    if (illakiya_engine_finish_composing(engine_, &edit) ==
+        ILLAKIYA_STATUS_OK) {
+        apply(edit);
+    }
+}
+
+void State::reset() {
+    candidates_.clear();
+    resync_ = true;
+    ic_->inputPanel().reset();
+    ic_->updatePreedit();
+    ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+}
+
+void State::dictionaryReady() {
+    if (!engine_) {
+        return;
+    }
+    updateCandidates();
+    ic_->updateUserInterface(fcitx::UserInterfaceComponent::InputPanel);
+}
+
+// After a focus change or cursor move, re-enter the word before the cursor
+// so it can be continued or corrected, as the Android IME does
+void State::resync() {
+    resync_ = false;
+    const auto &flags = ic_->capabilityFlags();
+    illakiya_engine_set_input_context(
+        engine_, inputKind(flags),
+        flags.test(fcitx::CapabilityFlag::Sensitive));
+    if (!flags.test(fcitx::CapabilityFlag::SurroundingText) || isComposing()) {
+        return;
+    }
+    const auto &surrounding = ic_->surroundingText();
+    if (!surrounding.isValid() ||
+        surrounding.cursor() != surrounding.anchor()) {
+        return;
+    }
+    const std::string &text = surrounding.text();
+    size_t split =
+        fcitx::utf8::ncharByteLength(text.begin(), surrounding.cursor());
+    std::string before = text.substr(0, split);
+    std::string after = text.substr(split);
+    IllakiyaEdit edit;
+    if (illakiya_engine_set_surrounding_text(engine_, before.c_str(),
+                                             after.c_str(), &edit) ==
+        ILLAKIYA_STATUS_OK) {
+        apply(edit);
+    }
+}
+
+Engine::Engine(fcitx::Instance *instance) : instance_(instance) {
+    dispatcher_.attach(&instance_->eventLoop());
+    instance_->inputContextManager().registerProperty("illakiyaState",
+                                                      &factory_);
+}
+
+State *Engine::state(fcitx::InputContext *ic) {
+    return ic->propertyFor(&factory_);
+}
+
+void Engine::keyEvent(const fcitx::InputMethodEntry &,
+                      fcitx::KeyEvent &keyEvent) {
+    state(keyEvent.inputContext())->keyEvent(keyEvent);
+}
+
+void Engine::activate(const fcitx::InputMethodEntry &,
+                      fcitx::InputContextEvent &event) {
+    state(event.inputContext())->reset();
+}
+
+// Switching input method or leaving the field is a commit point
+void Engine::deactivate(const fcitx::InputMethodEntry &,
+                        fcitx::InputContextEvent &event) {
+    auto *state = this->state(event.inputContext());
+    state->commitComposing();
+    state->reset();
+}
+
+void Engine::reset(const fcitx::InputMethodEntry &,
+                   fcitx::InputContextEvent &event) {
+    auto *state = this->state(event.inputContext());
+    state->commitComposing();
+    state->reset();
+}
+
+} // namespace illakiya
+
+FCITX_ADDON_FACTORY(illakiya::Factory);
@@ -0,0 +1,94 @@
+// Fcitx5 input method for Illakiya: PM0100 Tamil on the Linux desktop,
+// driven through the core's C ABI (core-rust/include/illakiya.h).
+
+#ifndef ILLAKIYA_FCITX5_ENGINE_H
+#define ILLAKIYA_FCITX5_ENGINE_H
+
+#include <fcitx-utils/eventdispatcher.h>
+#include <fcitx/addonfactory.h>
+#include <fcitx/addonmanager.h>
+#include <fcitx/inputcontextproperty.h>
+#include <fcitx/inputmethodengine.h>
+#include <fcitx/instance.h>
+
+#include <memory>
+#include <string>
+#include <vector>
+
+#include "illakiya.h"
+
+namespace illakiya {
+
+// One input context's typing: its own core engine and lookup table
+class State : public fcitx::InputContextProperty {
+public:
+    // `dispatcher` runs the dictionary-ready refresh on the main loop
+    State(fcitx::InputContext *ic, fcitx::EventDispatcher *dispatcher);
+    ~State() override;
+
+    State(const State &) = delete;
+    State &operator=(const State &) = delete;
+
+    void keyEvent(fcitx::KeyEvent &event);
+    // Accept candidate `index`: the sandhi join first, if any, then words
+    void select(size_t index);
+    // Commit the composing word, if any
+    void commitComposing();
+    // Forget the cursor position: re-read the word around it before the
+    // next key
+    void reset();
+    // The dictionary finished loading: suggestions are available now
+    void dictionaryReady();
+
+private:
+    // Apply an edit in the documented order and refresh the candidates
+    void apply(IllakiyaEdit &edit);
+    void updateCandidates();
+    void resync();
+    bool isComposing() const;
+    // The client applies deleteSurroundingText; others ignore it
+    bool canDelete() const;
+
+    fcitx::InputContext *ic_;
+    IllakiyaEngine *engine_;
+    // Lookup table entries; the first is the sandhi join when `sandhi_`
+    std::vector<std::string> candidates_;
+    bool sandhi_ = false;
+    bool resync_ = true;
+    // Expires with the state, so a late ready callback finds it gone
+    std::shared_ptr<State *> self_;
+};
+
+class Engine : public fcitx::InputMethodEngineV2 {
+public:
+    explicit Engine(fcitx::Instance *instance);
+
+    void keyEvent(const fcitx::InputMethodEntry &entry,
+                  fcitx::KeyEvent &keyEvent) override;
+    void activate(const fcitx::InputMethodEntry &entry,
+                  fcitx::InputContextEvent &event) override;
+    void deactivate(const fcitx::InputMethodEntry &entry,
+                    fcitx::InputContextEvent &event) override;
+    void reset(const fcitx::InputMethodEntry &entry,
+               fcitx::InputContextEvent &event) override;
+
+private:
+    State *state(fcitx::InputContext *ic);
+
+    fcitx::Instance *instance_;
+    fcitx::EventDispatcher dispatcher_;
+    fcitx::FactoryFor<State> factory_{[this](fcitx::InputContext &ic) {
+        return new State(&ic, &dispatcher_);
+    }};
+};
+
+class Factory : public fcitx::AddonFactory {
+public:
+    fcitx::AddonInstance *create(fcitx::AddonManager *manager) override {
+        return new Engine(manager->instance());
+    }
+};
+
+} // namespace illakiya
+
+#endif // ILLAKIYA_FCITX5_ENGINE_H
@@ -0,0 +1,414 @@
+//! `org.freedesktop.IBus.Engine`: one input context's typing.
+//!
+//! Keys arrive by hardware position and go through the core's keymap, so a
+//! physical keyboard types PM0100 exactly as the on-screen keyboard does.
+//! The composing word is shown as preedit, committed on focus loss, and
+//! the sandhi join and word suggestions fill the lookup table.
+
+use crate::text;
+use illakiya_core::{EditResult, InputContext, InputKind, KeyboardEngine};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::Value;
+use zbus::{fdo, interface};
+
+/// Suggestions shown below the sandhi join, as on Android
+const SUGGESTIONS: u32 = 5;
+
+// IBusModifierType
+const SHIFT_MASK: u32 = 1 << 0;
+const CONTROL_MASK: u32 = 1 << 2;
+const MOD1_MASK: u32 = 1 << 3;
+const SUPER_MASK: u32 = 1 << 26;
+const META_MASK: u32 = 1 << 28;
+const RELEASE_MASK: u32 = 1 << 30;
+
+// Keysyms the engine handles itself
+const KEY_TAB: u32 = 0xff09;
+const KEY_RETURN: u32 = 0xff0d;
+const KEY_KP_ENTER: u32 = 0xff8d;
+const KEY_UP: u32 = 0xff52;
+const KEY_DOWN: u32 = 0xff54;
+/// evdev code of Backspace
+const CODE_BACKSPACE: u32 = 14;
+
+/// IBUS_ENGINE_PREEDIT_COMMIT: the client commits the preedit on focus loss
+const PREEDIT_COMMIT: u32 = 1;
+/// IBUS_CAP_SURROUNDING_TEXT
+const CAP_SURROUNDING_TEXT: u32 = 1 << 5;
+
+// IBusInputPurpose and IBusInputHints
+const PURPOSE_DIGITS: u32 = 2;
+const PURPOSE_NUMBER: u32 = 3;
+const PURPOSE_PHONE: u32 = 4;
+const PURPOSE_URL: u32 = 5;
+const PURPOSE_EMAIL: u32 = 6;
+const PURPOSE_PASSWORD: u32 = 8;
+const PURPOSE_PIN: u32 = 9;
+const HINT_PRIVATE: u32 = 1 << 11;
+
+/// Map an IBus content type onto the engine's input context
+pub fn input_context(purpose: u32, hints: u32) -> InputContext {
+    let kind = match purpose {
+        PURPOSE_PASSWORD | PURPOSE_PIN => InputKind::Password,
+        PURPOSE_URL => InputKind::Url,
+        PURPOSE_EMAIL => InputKind::Email,
+        PURPOSE_DIGITS | PURPOSE_NUMBER | PURPOSE_PHONE => InputKind::Number,
+        _ => InputKind::Text,
+    };
+    InputContext { kind, no_learning: hints & HINT_PRIVATE != 0 }
+}
+
+/// Lookup table entries: the sandhi join, marked, then word suggestions.
+/// The join replaces the committed word before it, so it is only offered
+/// when the client can delete surrounding text.
+fn lookup_entries(engine: &KeyboardEngine, can_delete: bool) -> (bool, Vec<String>) {
+    let sandhi = engine.get_sandhi_suggestion().filter(|_| can_delete);
+    let entries = sandhi.iter()
+        .map(|join| format!("⚡ {join}"))
+        .chain(engine.get_suggestions(SUGGESTIONS))
+        .collect();
+    (sandhi.is_some(), entries)
+}
+
+/// Modifier keysyms (Shift_L..Hyper_R, ISO_Level*_Shift): pressing one
+/// alone must not end the word
+fn is_modifier(keyval: u32) -> bool {
+    (0xffe1..=0xffee).contains(&keyval) || (0xfe01..=0xfe0f).contains(&keyval)
+}
+
+pub struct Engine {
+    engine: KeyboardEngine,
+    /// Lookup table entries; the first is the sandhi join when `sandhi`
+    candidates: Vec<String>,
+    sandhi: bool,
+    cursor: u32,
+    capabilities: u32,
+    content_type: (u32, u32),
+    /// The cursor may have moved: take the word around it from the next
+    /// surrounding text
+    resync: bool,
+}
+
+impl Engine {
+    pub fn new(engine: KeyboardEngine) -> Self {
+        Self {
+            engine,
+            candidates: Vec::new(),
+            sandhi: false,
+            cursor: 0,
+            capabilities: 0,
+            content_type: (0, 0),
+            resync: true,
+        }
+    }
+
+    /// Apply `edit` in the documented order: drop the preedit, delete
+    /// around the cursor, commit, show the new preedit
+    async fn apply(&mut self, emitter: &SignalEmitter<'_>, edit: EditResult) -> zbus::Result<()> {
+        let deletes = edit.delete_before + edit.delete_after;
+        if deletes > 0 || !edit.commit.is_empty() {
+            Self::update_preedit_text(emitter, text::text("", false), 0, false, PREEDIT_COMMIT).await?;
+        }
+        if deletes > 0 {
+            Self::delete_surrounding_text(emitter, -(edit.delete_before as i32), deletes).await?;
+        }
+        if !edit.commit.is_empty() {
+            Self::commit_text(emitter, text::text(&edit.commit, false)).await?;
+        }
+        let cursor = edit.composing.chars().count() as u32;
+        let visible = !edit.composing.is_empty();
+        Self::update_preedit_text(emitter, text::text(&edit.composing, true), cursor, visible, PREEDIT_COMMIT).await?;
+        self.refresh_candidates(emitter).await
+    }
+
+    /// Commit the composing word, if any
+    async fn commit_composing(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        if self.engine.get_composing().is_empty() {
+            return Ok(());
+        }
+        let edit = self.engine.finish_composing();
+        self.apply(emitter, edit).await
+    }
+
+    /// The client applies `DeleteSurroundingText`; others ignore it
+    fn can_delete(&self) -> bool {
+        self.capabilities & CAP_SURROUNDING_TEXT != 0
+    }
+
+    async fn refresh_candidates(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        (self.sandhi, self.candidates) = lookup_entries(&self.engine, self.can_delete());
+        self.cursor = 0;
+        self.show_candidates(emitter).await
+    }
+
+    async fn show_candidates(&self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        if self.candidates.is_empty() {
+            return Self::hide_lookup_table(emitter).await;
+        }
+        Self::update_lookup_table(emitter, text::lookup_table(&self.candidates, self.cursor), true).await
+    }
+
+    async fn select(&mut self, emitter: &SignalEmitter<'_>, index: u32) -> zbus::Result<bool> {
+        let Some(candidate) = self.candidates.get(index as usize) else { return Ok(false) };
+        let edit = if index == 0 && self.sandhi {
+            self.engine.apply_sandhi()
+        } else {
+            self.engine.accept_suggestion(candidate)
+        };
+        self.apply(emitter, edit).await?;
+        Ok(true)
+    }
+
+    async fn move_cursor(&mut self, emitter: &SignalEmitter<'_>, down: bool) -> zbus::Result<()> {
+        let last = self.candidates.len().saturating_sub(1) as u32;
+        self.cursor = if down { (self.cursor + 1).min(last) } else { self.cursor.saturating_sub(1) };
+        self.show_candidates(emitter).await
+    }
+
+    /// The client keeps a COMMIT-mode preedit as text, so the engine only
+    /// forgets its composing word
+    async fn drop_composing(&mut self, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        self.engine.finish_composing();
+        self.candidates.clear();
+        self.resync = true;
+        Self::hide_lookup_table(emitter).await
+    }
+}
+
+#[interface(name = "org.freedesktop.IBus.Engine")]
+impl Engine {
+    async fn process_key_event(
+        &mut self,
+        keyval: u32,
+        keycode: u32,
+        state: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<bool> {
+        if state & RELEASE_MASK != 0 {
+            return Ok(false);
+        }
+        // Shortcuts act on committed text
+        if state & (CONTROL_MASK | MOD1_MASK | SUPER_MASK | META_MASK) != 0 {
+            self.commit_composing(&emitter).await?;
+            return Ok(false);
+        }
+        if !self.candidates.is_empty() {
+            match keyval {
+                KEY_TAB => return Ok(self.select(&emitter, self.cursor).await?),
+                KEY_UP | KEY_DOWN => {
+                    self.move_cursor(&emitter, keyval == KEY_DOWN).await?;
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+        // Enter stays a real key press: single-line fields submit on it
+        if keyval == KEY_RETURN || keyval == KEY_KP_ENTER {
+            self.commit_composing(&emitter).await?;
+            return Ok(false);
+        }
+        let composing = !self.engine.get_composing().is_empty();
+        if let Some(edit) = self.engine.process_key_code(keycode, state & SHIFT_MASK != 0) {
+            // Deleting one committed character is what the client's own
+            // Backspace does, and works without surrounding text
+            let plain_backspace = keycode == CODE_BACKSPACE && !composing
+                && edit.delete_before <= 1 && edit.delete_after == 0
+                && edit.commit.is_empty() && edit.composing.is_empty();
+            if plain_backspace {
+                self.refresh_candidates(&emitter).await?;
+                return Ok(false);
+            }
+            // A longer delete (reverting an autocorrection) would be ignored
+            // and leave both texts: let the client delete one character and
+            // start the engine over from there
+            if keycode == CODE_BACKSPACE && !composing && !self.can_delete() {
+                self.engine.reset();
+                self.refresh_candidates(&emitter).await?;
+                return Ok(false);
+            }
+            self.apply(&emitter, edit).await?;
+            return Ok(true);
+        }
+        if !is_modifier(keyval) {
+            self.commit_composing(&emitter).await?;
+        }
+        Ok(false)
+    }
+
+    async fn candidate_clicked(
+        &mut self,
+        index: u32,
+        _button: u32,
+        _state: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.select(&emitter, index).await?;
+        Ok(())
+    }
+
+    async fn cursor_up(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        Ok(self.move_cursor(&emitter, false).await?)
+    }
+
+    async fn cursor_down(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        Ok(self.move_cursor(&emitter, true).await?)
+    }
+
+    /// All candidates fit on one page
+    fn page_up(&self) {}
+
+    fn page_down(&self) {}
+
+    async fn focus_in(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        self.resync = true;
+        if self.can_delete() {
+            Self::require_surrounding_text(&emitter).await?;
+        }
+        Ok(())
+    }
+
+    async fn focus_out(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        Ok(self.drop_composing(&emitter).await?)
+    }
+
+    /// Sent when the cursor moves or the field is cleared
+    async fn reset(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        Ok(self.drop_composing(&emitter).await?)
+    }
+
+    fn enable(&mut self) {
+        self.resync = true;
+    }
+
+    async fn disable(&mut self, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> fdo::Result<()> {
+        Ok(self.drop_composing(&emitter).await?)
+    }
+
+    fn set_capabilities(&mut self, caps: u32) {
+        self.capabilities = caps;
+    }
+
+    fn set_cursor_location(&self, _x: i32, _y: i32, _w: i32, _h: i32) {}
+
+    /// After a focus change or cursor move, re-enter the word before the
+    /// cursor so it can be continued or corrected
+    async fn set_surrounding_text(
+        &mut self,
+        text: Value<'_>,
+        cursor_pos: u32,
+        anchor_pos: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if !self.resync || cursor_pos != anchor_pos || !self.engine.get_composing().is_empty() {
+            return Ok(());
+        }
+        self.resync = false;
+        let Some(text) = text::text_string(&text) else { return Ok(()) };
+        let split = text.char_indices().nth(cursor_pos as usize).map_or(text.len(), |(i, _)| i);
+        let (before, after) = text.split_at(split);
+        let edit = self.engine.set_surrounding_text(before, after);
+        Ok(self.apply(&emitter, edit).await?)
+    }
+
+    fn property_activate(&self, _name: &str, _state: u32) {}
+
+    fn property_show(&self, _name: &str) {}
+
+    fn property_hide(&self, _name: &str) {}
+
+    #[zbus(property)]
+    fn content_type(&self) -> (u32, u32) {
+        self.content_type
+    }
+
+    #[zbus(property)]
+    fn set_content_type(&mut self, value: (u32, u32)) {
+        self.content_type = value;
+        self.engine.set_input_context(input_context(value.0, value.1));
+    }
+
+    /// Use FocusIn/FocusOut rather than their per-client variants
+    #[zbus(property)]
+    fn focus_id(&self) -> (bool,) {
+        (false,)
+    }
+
+    #[zbus(property)]
+    fn active_surrounding_text(&self) -> (bool,) {
+        (true,)
+    }
+
+    #[zbus(signal)]
+    async fn commit_text(emitter: &SignalEmitter<'_>, text: Value<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn update_preedit_text(
+        emitter: &SignalEmitter<'_>,
+        text: Value<'_>,
+        cursor_pos: u32,
+        visible: bool,
+        mode: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn update_lookup_table(emitter: &SignalEmitter<'_>, table: Value<'_>, visible: bool) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn hide_lookup_table(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn delete_surrounding_text(emitter: &SignalEmitter<'_>, offset: i32, nchars: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn require_surrounding_text(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+/// `org.freedesktop.IBus.Service`, served next to each engine
+pub struct Service;
+
+#[interface(name = "org.freedesktop.IBus.Service")]
+impl Service {
+    /// The input context is gone: drop its engine
+    async fn destroy(
+        &self,
+        #[zbus(object_server)] server: &zbus::ObjectServer,
+        #[zbus(header)] header: zbus::message::Header<'_>,
+    ) -> fdo::Result<()> {
+        let Some(path) = header.path() else { return Ok(()) };
+        let path = path.to_owned();
+        server.remove::<Engine, _>(&path).await?;
+        server.remove::<Service, _>(&path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use illakiya_core::resources::Resources;
+
+    #[test]
+    fn test_input_context() {
+        assert_eq!(input_context(PURPOSE_PIN, 0).kind, InputKind::Password);
+        assert_eq!(input_context(PURPOSE_EMAIL, 0).kind, InputKind::Email);
+        assert_eq!(input_context(PURPOSE_PHONE, 0).kind, InputKind::Number);
+        let private = input_context(0, HINT_PRIVATE);
+        assert_eq!(private.kind, InputKind::Text);
+        assert!(private.no_learning);
+    }
+
+    #[test]
+    fn test_sandhi_needs_surrounding_text() {
+        let engine = KeyboardEngine::with_resources(Resources::pm0100().unwrap());
+        // பூ + கொடி
+        for key in ["o", "nedil", "c", "space", "q", "b", "t", "x"] {
+            engine.process_input(key);
+        }
+        let (sandhi, entries) = lookup_entries(&engine, true);
+        assert!(sandhi);
+        assert_eq!(entries[0], "⚡ பூக்கொடி");
+        let (sandhi, entries) = lookup_entries(&engine, false);
+        assert!(!sandhi);
+        assert!(!entries.iter().any(|e| e.starts_with('⚡')));
+    }
+}
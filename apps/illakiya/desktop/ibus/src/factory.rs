@@ -0,0 +1,160 @@
+//! `org.freedesktop.IBus.Factory`: ibus-daemon asks it for one engine per
+//! input context. Engines share the layout and word list.
+
+use crate::engine::{Engine, Service};
+use illakiya_core::resources::Resources;
+use illakiya_core::KeyboardEngine;
+use std::sync::Arc;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{fdo, interface, ObjectServer};
+
+/// Bus name of the component (see illakiya.xml)
+pub const BUS_NAME: &str = "org.freedesktop.IBus.Illakiya";
+pub const FACTORY_PATH: &str = "/org/freedesktop/IBus/Factory";
+/// Engine name of the component
+pub const ENGINE_NAME: &str = "illakiya";
+
+pub struct Factory {
+    resources: Arc<Resources>,
+    created: u32,
+}
+
+impl Factory {
+    pub fn new(resources: Arc<Resources>) -> Self {
+        Self { resources, created: 0 }
+    }
+}
+
+#[interface(name = "org.freedesktop.IBus.Factory")]
+impl Factory {
+    async fn create_engine(
+        &mut self,
+        name: &str,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<OwnedObjectPath> {
+        if name != ENGINE_NAME {
+            return Err(fdo::Error::InvalidArgs(format!("no engine named {name}")));
+        }
+        self.created += 1;
+        let path = OwnedObjectPath::try_from(format!("/org/freedesktop/IBus/Engine/{}", self.created))
+            .map_err(zbus::Error::from)?;
+        let engine = KeyboardEngine::with_resources(self.resources.clone());
+        server.at(&path, Engine::new(engine)).await?;
+        server.at(&path, Service).await?;
+        Ok(path)
+    }
+}
+
+/// Connect to the bus at `address` and serve the factory. The dictionary
+/// loads in the background; typing works meanwhile.
+pub fn serve(address: &str, resources: Arc<Resources>) -> zbus::Result<zbus::blocking::Connection> {
+    resources.load_in_background();
+    zbus::blocking::connection::Builder::address(address)?
+        .name(BUS_NAME)?
+        .serve_at(FACTORY_PATH, Factory::new(resources))?
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::mpsc;
+    use std::time::Duration;
+    use zbus::blocking::{Connection, MessageIterator, Proxy};
+    use zbus::zvariant::Value;
+    use zbus::MatchRule;
+
+    /// A private session bus, stopped on drop
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            Some(Self { daemon, address: address.trim().to_string() })
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Press the key at evdev `code`
+    fn press(engine: &Proxy, code: u32, shift: bool) -> bool {
+        // Keysyms only matter for keys the keymap does not cover
+        engine.call("ProcessKeyEvent", &(0u32, code, shift as u32)).unwrap()
+    }
+
+    #[test]
+    fn test_engine_over_private_bus() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon not found; skipping");
+            return;
+        };
+        let _server = serve(&bus.address, Resources::pm0100().unwrap()).unwrap();
+
+        let client: Connection = zbus::blocking::connection::Builder::address(bus.address.as_str())
+            .unwrap().build().unwrap();
+        let factory = Proxy::new(&client, BUS_NAME, FACTORY_PATH, "org.freedesktop.IBus.Factory").unwrap();
+        let path: OwnedObjectPath = factory.call("CreateEngine", &(ENGINE_NAME,)).unwrap();
+        assert!(factory.call::<_, _, OwnedObjectPath>("CreateEngine", &("pinyin",)).is_err());
+
+        // Collect the engine's signals on a thread, as (member, body text)
+        let rule = MatchRule::builder().msg_type(zbus::message::Type::Signal)
+            .path(path.as_str()).unwrap().build();
+        let signals = MessageIterator::for_match_rule(rule, &client, None).unwrap();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for message in signals.flatten() {
+                let member = message.header().member().map(|m| m.to_string()).unwrap_or_default();
+                let body = message.body();
+                let text = match member.as_str() {
+                    "CommitText" => body.deserialize::<Value>().ok().and_then(|v| text::text_string(&v)),
+                    "UpdatePreeditText" => body.deserialize::<(Value, u32, bool, u32)>().ok()
+                        .and_then(|(v, ..)| text::text_string(&v)),
+                    _ => None,
+                };
+                if tx.send((member, text)).is_err() {
+                    break;
+                }
+            }
+        });
+        let next = |member: &str| loop {
+            let (m, text) = rx.recv_timeout(Duration::from_secs(5)).expect("signal");
+            if m == member {
+                return text.unwrap_or_default();
+            }
+        };
+
+        let engine = Proxy::new(&client, BUS_NAME, path.as_str(), "org.freedesktop.IBus.Engine").unwrap();
+        // u types த், a pending consonant; z adds அ and makes த
+        assert!(press(&engine, 22, false));
+        assert_eq!(next("UpdatePreeditText"), "த்");
+        assert!(press(&engine, 44, false));
+        assert_eq!(next("UpdatePreeditText"), "த");
+        // Space commits the word
+        assert!(press(&engine, 57, false));
+        assert_eq!(next("CommitText"), "த ");
+        // Digits are not PM0100 keys: the client types them itself
+        assert!(!press(&engine, 2, false));
+
+        let service = Proxy::new(&client, BUS_NAME, path.as_str(), "org.freedesktop.IBus.Service").unwrap();
+        service.call::<_, _, ()>("Destroy", &()).unwrap();
+        assert!(engine.call::<_, _, bool>("ProcessKeyEvent", &(0u32, 22u32, 0u32)).is_err());
+    }
+}
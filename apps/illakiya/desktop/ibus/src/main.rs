@@ -0,0 +1,51 @@
+//! IBus engine for Illakiya: PM0100 Tamil on the Linux desktop.
+//!
+//! ibus-daemon starts this process as described in `illakiya.xml` (the
+//! `--ibus` flag is that convention; nothing else is accepted). It joins
+//! the IBus bus under the component's name and serves the engine factory
+//! until the bus goes away.
+
+mod engine;
+mod factory;
+mod text;
+
+use illakiya_core::resources::Resources;
+use std::process::{Command, ExitCode};
+
+/// The IBus bus: `IBUS_ADDRESS`, or what `ibus address` reports
+fn bus_address() -> Option<String> {
+    if let Ok(address) = std::env::var("IBUS_ADDRESS") {
+        return Some(address);
+    }
+    let output = Command::new("ibus").arg("address").output().ok()?;
+    let address = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !address.is_empty() && address != "(null)").then_some(address)
+}
+
+fn main() -> ExitCode {
+    let Some(address) = bus_address() else {
+        eprintln!("ibus-engine-illakiya: IBus is not running");
+        return ExitCode::FAILURE;
+    };
+    let resources = match Resources::pm0100_layout() {
+        Ok(resources) => resources,
+        Err(err) => {
+            eprintln!("ibus-engine-illakiya: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let connection = match factory::serve(&address, resources) {
+        Ok(connection) => connection,
+        Err(err) => {
+            eprintln!("ibus-engine-illakiya: {address}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    // Requests are served on zbus's own thread; wait for the bus to close
+    for message in zbus::blocking::MessageIterator::from(&connection) {
+        if message.is_err() {
+            break;
+        }
+    }
+    ExitCode::SUCCESS
+}
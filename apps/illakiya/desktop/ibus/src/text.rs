@@ -0,0 +1,89 @@
+//! IBus serialisation of text and lookup tables.
+//!
+//! IBus sends its objects as variants of a structure that starts with the
+//! type name and an (unused) attachment dictionary, followed by the
+//! object's own fields.
+
+use std::collections::HashMap;
+use zbus::zvariant::{StructureBuilder, Value};
+
+/// IBUS_ATTR_TYPE_UNDERLINE
+const ATTR_UNDERLINE: u32 = 1;
+/// IBUS_ATTR_UNDERLINE_SINGLE
+const UNDERLINE_SINGLE: u32 = 1;
+/// IBUS_ORIENTATION_SYSTEM: horizontal or vertical, as the user chose
+const ORIENTATION_SYSTEM: i32 = 2;
+
+fn serializable(name: &str) -> StructureBuilder<'static> {
+    StructureBuilder::new()
+        .add_field(name.to_string())
+        .add_field(HashMap::<String, Value<'static>>::new())
+}
+
+fn variant(value: Value<'static>) -> Value<'static> {
+    Value::Value(Box::new(value))
+}
+
+fn structure(builder: StructureBuilder<'static>) -> Value<'static> {
+    // Never empty: every builder starts with the type name
+    Value::Structure(builder.build().expect("IBus object has fields"))
+}
+
+/// IBusText, underlined throughout when it is preedit
+pub fn text(s: &str, underline: bool) -> Value<'static> {
+    let mut attributes = Vec::new();
+    if underline && !s.is_empty() {
+        let end = s.chars().count() as u32;
+        attributes.push(variant(structure(serializable("IBusAttribute")
+            .add_field(ATTR_UNDERLINE)
+            .add_field(UNDERLINE_SINGLE)
+            .add_field(0u32)
+            .add_field(end))));
+    }
+    let attr_list = structure(serializable("IBusAttrList").add_field(attributes));
+    structure(serializable("IBusText")
+        .add_field(s.to_string())
+        .append_field(variant(attr_list)))
+}
+
+/// IBusLookupTable showing all of `candidates` on one page
+pub fn lookup_table(candidates: &[String], cursor: u32) -> Value<'static> {
+    let texts: Vec<Value<'static>> = candidates.iter()
+        .map(|c| variant(text(c, false)))
+        .collect();
+    structure(serializable("IBusLookupTable")
+        .add_field(candidates.len().max(1) as u32)
+        .add_field(cursor)
+        .add_field(true)
+        .add_field(false)
+        .add_field(ORIENTATION_SYSTEM)
+        .add_field(texts)
+        .add_field(Vec::<Value<'static>>::new()))
+}
+
+/// The string of an IBusText
+pub fn text_string(value: &Value<'_>) -> Option<String> {
+    let value = match value {
+        Value::Value(inner) => inner.as_ref(),
+        other => other,
+    };
+    let Value::Structure(s) = value else { return None };
+    match s.fields().get(2) {
+        Some(Value::Str(text)) => Some(text.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signatures() {
+        let preedit = text("தமி", true);
+        assert_eq!(preedit.value_signature().to_string(), "(sa{sv}sv)");
+        assert_eq!(text_string(&preedit).as_deref(), Some("தமி"));
+        let table = lookup_table(&["தமிழ்".to_string()], 0);
+        assert_eq!(table.value_signature().to_string(), "(sa{sv}uubbiavav)");
+    }
+}
@@ -0,0 +1,16 @@
+#!/bin/bash
+# Generate the C header for the C ABI using cbindgen
+set -e
+
+RUST_DIR="$(dirname "$0")/../core-rust"
+
+if ! command -v cbindgen &> /dev/null; then
+    echo "cbindgen is not installed. Run: cargo install cbindgen"
+    exit 1
+fi
+
+echo "Generating include/illakiya.h..."
+cd "$RUST_DIR"
+cbindgen --config cbindgen.toml --output include/illakiya.h
+
+echo "Header generated successfully!"
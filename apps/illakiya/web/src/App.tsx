@@ -1,4 +1,6 @@
-import { useEffect, useMemo, useRef, useState } from 'react';
+import { useCallback, useEffect, useMemo, useRef, useState } from 'react';
+import { applyEdit, useIllakiya } from './illakiya';
+import type { Edit, KeyboardEngine } from './wasm/illakiya_core';
 
 type AppView = 'editor' | 'tutor';
 
@@ -39,44 +41,14 @@ const lessons: Lesson[] = [
   },
 ];
 
-const keyboardRows: string[][] = [
-  ['க்', 'ச்', 'ட்', 'த்', 'ப்', 'ற்'],
-  ['ய்', 'ர்', 'ல்', 'வ்', 'ழ்', 'ள்'],
-  ['ங்', 'ஞ்', 'ண்', 'ந்', 'ம்', 'ன்'],
-  ['அ', 'இ', 'உ', 'எ', 'ஒ'],
-];
-
-const meiToUyirBase: Record<string, string> = {
-  'க்': 'க',
-  'ச்': 'ச',
-  'ட்': 'ட',
-  'த்': 'த',
-  'ப்': 'ப',
-  'ற்': 'ற',
-  'ய்': 'ய',
-  'ர்': 'ர',
-  'ல்': 'ல',
-  'வ்': 'வ',
-  'ழ்': 'ழ',
-  'ள்': 'ள',
-  'ங்': 'ங',
-  'ஞ்': 'ஞ',
-  'ண்': 'ண',
-  'ந்': 'ந',
-  'ம்': 'ம',
-  'ன்': 'ன',
+type KeyView = {
+  row: number;
+  key: string;
+  label: string;
+  width: number;
+  valid: boolean;
 };
 
-const vowelSigns: Record<string, string> = {
-  அ: '',
-  இ: 'ி',
-  உ: 'ு',
-  எ: 'ெ',
-  ஒ: 'ொ',
-};
-
-const shortVowels = new Set(Object.keys(vowelSigns));
-
 function createNote(partial?: Partial<Note>): Note {
   const now = Date.now();
   return {
@@ -141,6 +113,21 @@ export default function App() {
   const activeTags = useMemo(() => extractTags(activeNote?.content ?? ''), [activeNote]);
   const editorRef = useRef<HTMLTextAreaElement | null>(null);
 
+  const { engine, ready } = useIllakiya();
+  const [keys, setKeys] = useState<KeyView[]>([]);
+  const [suggestions, setSuggestions] = useState<string[]>([]);
+  const [sandhi, setSandhi] = useState<string | null>(null);
+  /** UTF-16 length of the composing word, which ends at the caret */
+  const composingRef = useRef(0);
+  /** Caret the last edit left, so our own selection changes are not resynced */
+  const expectedCaretRef = useRef<number | null>(null);
+
+  useEffect(() => {
+    engine?.reset();
+    composingRef.current = 0;
+    expectedCaretRef.current = null;
+  }, [engine, activeNoteId]);
+
   useEffect(() => {
     localStorage.setItem(STORAGE_KEY, JSON.stringify(notes));
   }, [notes]);
@@ -190,76 +177,65 @@ export default function App() {
     });
   };
 
-  const composePm0100 = (before: string, key: string): { before: string; inserted: string } => {
-    if (!shortVowels.has(key)) {
-      return { before, inserted: key };
-    }
-
-    const meiMatch = Object.keys(meiToUyirBase).find((mei) => before.endsWith(mei));
-    if (!meiMatch) {
-      return { before, inserted: key };
-    }
-
-    const replacedBefore = before.slice(0, -meiMatch.length);
-    const uyirBase = meiToUyirBase[meiMatch];
-    const sign = vowelSigns[key] ?? '';
-    return { before: replacedBefore, inserted: `${uyirBase}${sign}` };
-  };
-
-  const insertTextAtCursor = (value: string) => {
-    const editor = editorRef.current;
-    if (!activeNote || !editor) {
+  /** Re-read the key labels and suggestions after the engine changed state. */
+  const refresh = useCallback(() => {
+    if (!engine) {
       return;
     }
+    setKeys(
+      engine.keys().map((key) => {
+        const view = { row: key.row, key: key.key, label: key.primary, width: key.width, valid: key.valid };
+        key.free();
+        return view;
+      })
+    );
+    setSuggestions(engine.suggestions(3));
+    setSandhi(engine.sandhiSuggestion() ?? null);
+  }, [engine]);
 
-    const start = editor.selectionStart ?? activeNote.content.length;
-    const end = editor.selectionEnd ?? activeNote.content.length;
-    const before = activeNote.content.slice(0, start);
-    const after = activeNote.content.slice(end);
-
-    const composed = composePm0100(before, value);
-    const next = `${composed.before}${composed.inserted}${after}`;
-    const caret = composed.before.length + composed.inserted.length;
-    updateActiveAndSelection(next, caret);
-  };
+  useEffect(refresh, [refresh, ready]);
 
-  const backspaceAtCursor = () => {
+  /** Run an engine call and apply its edit at the caret. */
+  const run = (op: (engine: KeyboardEngine) => Edit) => {
     const editor = editorRef.current;
-    if (!activeNote || !editor) {
+    if (!engine || !activeNote || !editor) {
       return;
     }
 
-    const start = editor.selectionStart ?? activeNote.content.length;
-    const end = editor.selectionEnd ?? activeNote.content.length;
-
-    if (start !== end) {
-      const next = `${activeNote.content.slice(0, start)}${activeNote.content.slice(end)}`;
-      updateActiveAndSelection(next, start);
-      return;
-    }
+    const edit = op(engine);
+    const next = applyEdit(
+      { text: activeNote.content, caret: editor.selectionStart ?? activeNote.content.length, composing: composingRef.current },
+      edit
+    );
+    edit.free();
+    composingRef.current = next.composing;
+    expectedCaretRef.current = next.caret;
+    updateActiveAndSelection(next.text, next.caret);
+    refresh();
+  };
 
-    if (start <= 0) {
+  /**
+   * The text or caret changed outside the engine (physical keyboard, click):
+   * hand it the text around the caret so the word there becomes composing.
+   */
+  const resync = (content: string, caret: number) => {
+    if (!engine) {
       return;
     }
+    const edit = engine.setSurroundingText(content.slice(0, caret), content.slice(caret));
+    composingRef.current = applyEdit({ text: content, caret, composing: 0 }, edit).composing;
+    expectedCaretRef.current = caret;
+    edit.free();
+    refresh();
+  };
 
-    const before = activeNote.content.slice(0, start);
-    const after = activeNote.content.slice(start);
-
-    for (const [mei, base] of Object.entries(meiToUyirBase)) {
-      for (const sign of Object.values(vowelSigns)) {
-        const composed = `${base}${sign}`;
-        if (composed.length > 0 && before.endsWith(composed)) {
-          const reverted = `${before.slice(0, -composed.length)}${mei}`;
-          const next = `${reverted}${after}`;
-          updateActiveAndSelection(next, reverted.length);
-          return;
-        }
-      }
+  const keyRows = useMemo(() => {
+    const rows: KeyView[][] = [];
+    for (const key of keys) {
+      (rows[key.row] ??= []).push(key);
     }
-
-    const next = `${activeNote.content.slice(0, start - 1)}${activeNote.content.slice(start)}`;
-    updateActiveAndSelection(next, start - 1);
-  };
+    return rows;
+  }, [keys]);
 
   const exportText = () => {
     if (!activeNote) {
@@ -360,7 +336,16 @@ export default function App() {
                   ref={editorRef}
                   className="markdownEditor"
                   value={activeNote?.content ?? ''}
-                  onChange={(event) => updateActiveNote(event.target.value)}
+                  onChange={(event) => {
+                    updateActiveNote(event.target.value);
+                    resync(event.target.value, event.target.selectionStart);
+                  }}
+                  onSelect={(event) => {
+                    const caret = event.currentTarget.selectionStart;
+                    if (caret !== expectedCaretRef.current) {
+                      resync(event.currentTarget.value, caret);
+                    }
+                  }}
                   placeholder="PM0100 ஆதரவு கொண்ட தமிழ் குறிப்பேடு..."
                 />
                 <p className="saveHint">உள்ளூரில் தானாகச் சேமிக்கப்பட்டது</p>
@@ -369,20 +354,32 @@ export default function App() {
           </article>
 
           <aside className="keyboardDock">
+            <div className="suggestionStrip" aria-live="polite">
+              {sandhi ? (
+                <button className="sandhi" onClick={() => run((e) => e.applySandhi())}>
+                  ⚡ {sandhi}
+                </button>
+              ) : null}
+              {suggestions.map((word) => (
+                <button key={word} onClick={() => run((e) => e.acceptSuggestion(word))}>
+                  {word}
+                </button>
+              ))}
+              {!ready ? <span className="loadingHint">அகராதி ஏற்றப்படுகிறது…</span> : null}
+            </div>
             <div className="keyboardPanel attached big" aria-label="PM0100 விசைப்பலகை">
-              {keyboardRows.map((row, rowIndex) => (
+              {keyRows.map((row, rowIndex) => (
                 <div className="keyboardRow" key={`attach-row-${rowIndex}`}>
                   {row.map((key) => (
-                    <button key={`${rowIndex}-${key}`} onClick={() => insertTextAtCursor(key)}>
-                      {key}
+                    <button
+                      key={key.key}
+                      className={key.valid ? undefined : 'invalid'}
+                      style={{ flexGrow: key.width }}
+                      onClick={() => run((e) => (key.key === 'backspace' ? e.backspace(false) : e.keyTap(key.key)))}
+                    >
+                      {key.label}
                     </button>
                   ))}
-                  {rowIndex === keyboardRows.length - 1 ? (
-                    <>
-                      <button onClick={() => insertTextAtCursor(' ')}>⎵</button>
-                      <button onClick={backspaceAtCursor}>⌫</button>
-                    </>
-                  ) : null}
                 </div>
               ))}
             </div>
@@ -0,0 +1,71 @@
+import { useEffect, useState } from 'react';
+import init, { KeyboardEngine, type Edit } from './wasm/illakiya_core';
+
+/** Text of a textarea plus the engine's composing region before the caret. */
+export type EditorText = {
+  text: string;
+  caret: number;
+  /** UTF-16 length of the composing region, which ends at the caret */
+  composing: number;
+};
+
+/** Drop `count` Unicode scalar values from the end of `text`. */
+function dropScalars(text: string, count: number): string {
+  const chars = Array.from(text);
+  return chars.slice(0, Math.max(0, chars.length - count)).join('');
+}
+
+/** Keep all but the first `count` Unicode scalar values of `text`. */
+function skipScalars(text: string, count: number): string {
+  return Array.from(text).slice(count).join('');
+}
+
+/**
+ * Apply an engine edit in the documented order: drop the composing region,
+ * delete around the caret, insert the commit, then the new composing text.
+ */
+export function applyEdit(state: EditorText, edit: Edit): EditorText {
+  const before = state.text.slice(0, state.caret - state.composing);
+  const after = state.text.slice(state.caret);
+  const kept = dropScalars(before, edit.deleteBefore) + edit.commit + edit.composing;
+  return {
+    text: kept + skipScalars(after, edit.deleteAfter),
+    caret: kept.length,
+    composing: edit.composing.length,
+  };
+}
+
+/**
+ * The PM0100 engine compiled to WebAssembly: the same Rust core as the
+ * Android keyboard. Typing works once the module is instantiated; the word
+ * list is built after the first paint and `ready` turns true.
+ */
+export function useIllakiya(): { engine: KeyboardEngine | null; ready: boolean } {
+  const [engine, setEngine] = useState<KeyboardEngine | null>(null);
+  const [ready, setReady] = useState(false);
+
+  useEffect(() => {
+    let created: KeyboardEngine | null = null;
+    let cancelled = false;
+    init().then(() => {
+      if (cancelled) {
+        return;
+      }
+      created = new KeyboardEngine();
+      setEngine(created);
+      // Blocks for a cold start: let the keyboard paint first
+      setTimeout(() => {
+        if (!cancelled && created) {
+          created.loadDictionary();
+          setReady(true);
+        }
+      });
+    });
+    return () => {
+      cancelled = true;
+      created?.free();
+    };
+  }, []);
+
+  return { engine, ready };
+}